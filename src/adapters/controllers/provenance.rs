@@ -1,8 +1,10 @@
 use anyhow::Result;
-use chrono::prelude::*;
 use tracing::{debug, instrument};
 
-use crate::use_cases::{ProgramDataProvenance, SolanaQueries};
+use crate::{
+    entities::{DeploymentTimeline, LastDeployment, ProgramId, ProvenanceReport},
+    use_cases::{ProgramDataProvenance, SolanaQueries},
+};
 
 pub(crate) struct ProvenanceAdapter {
     use_case: ProgramDataProvenance,
@@ -15,16 +17,30 @@ impl ProvenanceAdapter {
     }
 
     #[instrument(skip(solana))]
-    pub fn new_with_gateway(solana: Box<dyn SolanaQueries>) -> Self {
-        let use_case = ProgramDataProvenance::new(solana);
+    pub fn new_with_gateway(solana: Box<dyn SolanaQueries>, strict: bool) -> Self {
+        let use_case = ProgramDataProvenance::new(solana, strict);
 
         Self::new(use_case)
     }
 
     #[instrument(skip(self))]
-    pub fn lookup_provenance(&self, program_id: &str) -> Result<DateTime<Utc>> {
+    pub fn lookup_provenance(&self, program_id: &ProgramId) -> Result<ProvenanceReport> {
         debug!("Beginning program provenance via use case lookup for {program_id}.");
 
         self.use_case.lookup_provenance(program_id)
     }
+
+    #[instrument(skip(self))]
+    pub fn lookup_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment> {
+        debug!("Beginning last deployment via use case lookup for {program_id}.");
+
+        self.use_case.lookup_last_deployment(program_id)
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline> {
+        debug!("Beginning deployment timeline via use case lookup for {program_id}.");
+
+        self.use_case.lookup_timeline(program_id)
+    }
 }
@@ -0,0 +1,547 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use tracing::{debug, instrument, trace, warn};
+
+use crate::entities::SolceptionError;
+
+const CLUSTERS_FILE: &str = "clusters.json";
+const HISTORIES_DIR: &str = "histories";
+const TRANSACTIONS_DIR: &str = "transactions";
+const COMPLETE_MARKER: &str = "complete.json";
+
+/// A crawl writes a page every few requests, so a run left incomplete for
+/// this long was abandoned by an interrupted crawl rather than still being
+/// written by another process.
+const ABANDONED_RUN_AGE: Duration = Duration::from_secs(60 * 60);
+
+type SignaturePage = Vec<RpcConfirmedTransactionStatusWithSignature>;
+
+/// A local cache of finalized ledger data for one cluster, keyed by the
+/// cluster's genesis hash so different RPC URLs for the same cluster share
+/// it.
+///
+/// Each address's history is stored as a series of crawl runs, each holding
+/// the pages of summaries it retrieved, newest first.  A run only counts once
+/// its completion marker is written, so an interrupted crawl is discarded
+/// rather than leaving a gap in the history.  Later runs crawl only until the
+/// newest signature of the latest run, and a run that does not continue from
+/// there, such as one completed by a concurrent crawl of the same
+/// transactions, is skipped.  Transaction details are stored one file per
+/// signature.
+pub(crate) struct TransactionCache {
+    dir: PathBuf,
+}
+
+/// Records which genesis hash each RPC URL served, so offline lookups can
+/// find a cluster's cache without asking the RPC node.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ClusterIndex {
+    clusters: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RunMarker {
+    newest_signature: String,
+    transactions: usize,
+
+    /// The newest signature of the run this one continues, if any.
+    #[serde(default)]
+    until: Option<String>,
+}
+
+impl TransactionCache {
+    /// Opens the cache for the cluster behind the RPC URL.  Online, the
+    /// genesis hash is asked of the RPC node and remembered for the URL;
+    /// offline, only a remembered genesis hash can be used.
+    #[instrument(skip(genesis_hash))]
+    pub fn open_for_cluster(
+        root: &Path,
+        rpc_url: &str,
+        offline: bool,
+        genesis_hash: impl FnOnce() -> Result<String>,
+    ) -> Result<Self> {
+        let index_path = root.join(CLUSTERS_FILE);
+        let mut index: ClusterIndex = read_json(&index_path)?.unwrap_or_default();
+
+        let genesis_hash = match (offline, index.clusters.get(rpc_url)) {
+            (true, Some(genesis_hash)) => genesis_hash.clone(),
+            (true, None) => {
+                return Err(SolceptionError::NotCached {
+                    what: format!("data for the RPC node {rpc_url} at {}", root.display()),
+                }
+                .into())
+            }
+            (false, _) => {
+                let genesis_hash = genesis_hash()?;
+                if index.clusters.get(rpc_url) != Some(&genesis_hash) {
+                    index
+                        .clusters
+                        .insert(rpc_url.to_string(), genesis_hash.clone());
+                    write_json(&index_path, &index)?;
+                }
+                genesis_hash
+            }
+        };
+        debug!(
+            "Using the cache for cluster {genesis_hash} at {}",
+            root.display()
+        );
+
+        Ok(Self {
+            dir: root.join(genesis_hash),
+        })
+    }
+
+    /// Returns the complete crawl runs cached for the address.
+    #[instrument(skip(self))]
+    pub fn history(&self, address: &Pubkey) -> Result<CachedHistory> {
+        let dir = self.history_dir(address);
+
+        let mut runs: Vec<(PathBuf, RunMarker)> = Vec::new();
+        for run in sorted_entries(&dir)? {
+            let Some(marker) = read_json::<RunMarker>(&run.join(COMPLETE_MARKER))? else {
+                trace!("Skipping incomplete crawl run {}", run.display());
+                continue;
+            };
+
+            let newest_signature = runs.last().map(|(_, marker)| &marker.newest_signature);
+            if marker.until.as_ref() != newest_signature {
+                debug!(
+                    "Skipping crawl run {} , which does not continue the runs before it.",
+                    run.display()
+                );
+                continue;
+            }
+            runs.push((run, marker));
+        }
+
+        Ok(CachedHistory { runs })
+    }
+
+    /// Starts a new crawl run for the address, continuing from the given
+    /// signature, and discards any earlier run that was abandoned.  Another
+    /// process may be crawling the same address, so each run claims a run
+    /// number of its own, and runs still being written are left alone.
+    #[instrument(skip(self))]
+    pub fn start_run(&self, address: &Pubkey, until: Option<&str>) -> Result<HistoryRun> {
+        let dir = self.history_dir(address);
+        fs::create_dir_all(&dir)?;
+
+        let mut next_run = 0;
+        for run in sorted_entries(&dir)? {
+            next_run = run_number(&run).map_or(next_run, |number| next_run.max(number + 1));
+            if is_abandoned_run(&run).unwrap_or(false) {
+                debug!("Removing abandoned crawl run {}", run.display());
+                if let Err(e) = remove(&run) {
+                    debug!("Failed to remove the abandoned crawl run: {e}");
+                }
+            }
+        }
+
+        loop {
+            let run = dir.join(format!("{next_run:08}"));
+            match fs::create_dir(&run) {
+                Ok(()) => {
+                    return Ok(HistoryRun {
+                        dir: run,
+                        pages: 0,
+                        transactions: 0,
+                        newest_signature: None,
+                        until: until.map(ToString::to_string),
+                    })
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => next_run += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub fn transaction(
+        &self,
+        signature: &str,
+    ) -> Option<EncodedConfirmedTransactionWithStatusMeta> {
+        match read_json(&self.transaction_path(signature)) {
+            Ok(txn) => txn,
+            Err(e) => {
+                debug!("Ignoring unreadable cached transaction {signature}: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn store_transaction(
+        &self,
+        signature: &str,
+        txn: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<()> {
+        write_json(&self.transaction_path(signature), txn)
+    }
+
+    fn history_dir(&self, address: &Pubkey) -> PathBuf {
+        self.dir.join(HISTORIES_DIR).join(address.to_string())
+    }
+
+    /// Transactions are sharded by the first characters of their signature
+    /// to keep directories small.
+    fn transaction_path(&self, signature: &str) -> PathBuf {
+        let shard = signature.get(..2).unwrap_or(signature);
+
+        self.dir
+            .join(TRANSACTIONS_DIR)
+            .join(shard)
+            .join(format!("{signature}.json"))
+    }
+}
+
+/// The complete crawl runs of an address, oldest run first.
+pub(crate) struct CachedHistory {
+    runs: Vec<(PathBuf, RunMarker)>,
+}
+
+impl CachedHistory {
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// The newest cached signature, which bounds the next crawl.
+    pub fn newest_signature(&self) -> Option<&str> {
+        self.runs
+            .last()
+            .map(|(_, marker)| marker.newest_signature.as_str())
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.runs
+            .iter()
+            .map(|(_, marker)| marker.transactions)
+            .sum()
+    }
+
+    /// Reads the cached pages newest first, one page at a time.
+    pub fn pages_newest_first(&self) -> Result<impl Iterator<Item = Result<SignaturePage>> + Send> {
+        let mut pages = Vec::new();
+        for (run, _) in self.runs.iter().rev() {
+            pages.extend(page_files(run)?);
+        }
+
+        Ok(pages.into_iter().map(|path| read_page(&path)))
+    }
+
+    /// Reads the cached pages oldest first, one page at a time, with each
+    /// page in slot order.
+    pub fn pages_oldest_first(&self) -> Result<impl Iterator<Item = Result<SignaturePage>> + Send> {
+        let mut pages = Vec::new();
+        for (run, _) in &self.runs {
+            pages.extend(page_files(run)?.into_iter().rev());
+        }
+
+        Ok(pages.into_iter().map(|path| {
+            read_page(&path).map(|mut page| {
+                page.reverse();
+                page
+            })
+        }))
+    }
+}
+
+/// A crawl run being written, one page at a time, newest first.
+pub(crate) struct HistoryRun {
+    dir: PathBuf,
+    pages: usize,
+    transactions: usize,
+    newest_signature: Option<String>,
+    until: Option<String>,
+}
+
+impl HistoryRun {
+    pub fn write_page(&mut self, page: &SignaturePage) -> Result<()> {
+        if page.is_empty() {
+            return Ok(());
+        }
+
+        write_json(&self.dir.join(format!("{:08}.json", self.pages)), page)?;
+        self.pages += 1;
+        self.transactions += page.len();
+        if self.newest_signature.is_none() {
+            self.newest_signature = page.first().map(|txn| txn.signature.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Marks the run as complete, or removes it when the crawl found nothing
+    /// new.
+    pub fn complete(self) -> Result<()> {
+        let Some(newest_signature) = self.newest_signature else {
+            fs::remove_dir_all(&self.dir)?;
+            return Ok(());
+        };
+
+        debug!(
+            "Cached {} new transaction summaries in {}",
+            self.transactions,
+            self.dir.display()
+        );
+        write_json(
+            &self.dir.join(COMPLETE_MARKER),
+            &RunMarker {
+                newest_signature,
+                transactions: self.transactions,
+                until: self.until,
+            },
+        )
+    }
+}
+
+/// The contents of the cache for one cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ClusterStats {
+    pub genesis_hash: String,
+    pub rpc_urls: Vec<String>,
+    pub histories: usize,
+    pub transactions: usize,
+    pub bytes: u64,
+}
+
+/// Summarizes the contents of the cache, one entry per cluster.
+#[instrument]
+pub(crate) fn cache_stats(root: &Path) -> Result<Vec<ClusterStats>> {
+    let index: ClusterIndex = read_json(&root.join(CLUSTERS_FILE))?.unwrap_or_default();
+
+    let mut stats = Vec::new();
+    for cluster in cluster_dirs(root)? {
+        let genesis_hash = file_name(&cluster);
+        let rpc_urls = index
+            .clusters
+            .iter()
+            .filter(|(_, hash)| **hash == genesis_hash)
+            .map(|(url, _)| url.clone())
+            .collect();
+
+        let mut transactions = 0;
+        for shard in sorted_entries(&cluster.join(TRANSACTIONS_DIR))? {
+            transactions += fs::read_dir(shard)?.count();
+        }
+
+        stats.push(ClusterStats {
+            histories: sorted_entries(&cluster.join(HISTORIES_DIR))?.len(),
+            transactions,
+            bytes: disk_usage(&cluster)?,
+            genesis_hash,
+            rpc_urls,
+        });
+    }
+
+    Ok(stats)
+}
+
+/// Removes address histories and transaction details not written for the
+/// given age, along with any abandoned crawl runs.  Histories are removed
+/// whole, since a history missing its older runs would look complete.
+/// Returns the number of bytes freed.
+#[instrument]
+pub(crate) fn prune_cache(root: &Path, older_than: Duration) -> Result<u64> {
+    let cutoff = SystemTime::now()
+        .checked_sub(older_than)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut freed = 0;
+
+    for cluster in cluster_dirs(root)? {
+        for history in sorted_entries(&cluster.join(HISTORIES_DIR))? {
+            if newest_modification(&history)? < cutoff {
+                freed += remove(&history)?;
+                continue;
+            }
+
+            for run in sorted_entries(&history)? {
+                if is_abandoned_run(&run)? {
+                    freed += remove(&run)?;
+                }
+            }
+        }
+
+        for shard in sorted_entries(&cluster.join(TRANSACTIONS_DIR))? {
+            for txn in fs::read_dir(&shard)? {
+                let path = txn?.path();
+                if fs::metadata(&path)?.modified()? < cutoff {
+                    freed += remove(&path)?;
+                }
+            }
+        }
+    }
+
+    Ok(freed)
+}
+
+/// Removes everything in the cache.  Returns the number of bytes freed.
+#[instrument]
+pub(crate) fn clear_cache(root: &Path) -> Result<u64> {
+    if !root.exists() {
+        return Ok(0);
+    }
+
+    remove(root)
+}
+
+fn read_page(path: &Path) -> Result<SignaturePage> {
+    read_json(path)?.ok_or_else(|| {
+        anyhow!(
+            "The cached page {} disappeared while it was being read.  Another solc process may \
+             have pruned or cleared the cache.",
+            path.display()
+        )
+    })
+}
+
+/// Lists the pages of a run in the order they were crawled, skipping the
+/// completion marker and any temporary file left by an interrupted write.
+fn page_files(run: &Path) -> Result<Vec<PathBuf>> {
+    Ok(sorted_entries(run)?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+                && path.file_name().is_some_and(|name| name != COMPLETE_MARKER)
+        })
+        .collect())
+}
+
+/// The cache root holds one directory per cluster named in the cluster
+/// index, beside anything else kept there, such as checkpoints.
+fn cluster_dirs(root: &Path) -> Result<Vec<PathBuf>> {
+    let index: ClusterIndex = read_json(&root.join(CLUSTERS_FILE))?.unwrap_or_default();
+
+    Ok(sorted_entries(root)?
+        .into_iter()
+        .filter(|path| {
+            path.is_dir() && index.clusters.values().any(|hash| *hash == file_name(path))
+        })
+        .collect())
+}
+
+/// An incomplete run that has not been written to for a while.
+fn is_abandoned_run(run: &Path) -> Result<bool> {
+    if run.join(COMPLETE_MARKER).exists() {
+        return Ok(false);
+    }
+
+    let age = SystemTime::now()
+        .duration_since(newest_modification(run)?)
+        .unwrap_or_default();
+
+    Ok(age >= ABANDONED_RUN_AGE)
+}
+
+fn run_number(run: &Path) -> Option<u64> {
+    file_name(run).parse().ok()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Lists a directory's entries in name order, treating a missing directory
+/// as empty.
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    Ok(paths)
+}
+
+pub(crate) fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Option<T>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+        anyhow!(
+            "Failed to parse the cached file {} .  Run `solc cache clear` if the cache was \
+             written by an incompatible version.  {e}",
+            path.display()
+        )
+    })
+}
+
+/// Writes to a temporary file first and renames it into place, so a reader
+/// never sees a partially written file.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec(value)?)?;
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+fn remove(path: &Path) -> Result<u64> {
+    let bytes = disk_usage(path)?;
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+
+    Ok(bytes)
+}
+
+fn disk_usage(path: &Path) -> Result<u64> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut bytes = 0;
+    for entry in fs::read_dir(path)? {
+        bytes += disk_usage(&entry?.path())?;
+    }
+
+    Ok(bytes)
+}
+
+fn newest_modification(path: &Path) -> Result<SystemTime> {
+    let metadata = fs::metadata(path)?;
+    let mut newest = metadata.modified()?;
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            newest = newest.max(newest_modification(&entry?.path())?);
+        }
+    }
+
+    Ok(newest)
+}
+
+/// Writes that fail only lose the chance to reuse the data later, so they
+/// are logged rather than failing the lookup.
+pub(crate) fn warn_on_write_failure(result: Result<()>, what: &str) {
+    if let Err(e) = result {
+        warn!("Failed to write {what} to the cache, so it will be fetched again next time: {e}");
+    }
+}
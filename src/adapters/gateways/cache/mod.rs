@@ -0,0 +1,12 @@
+pub mod interface;
+
+pub(crate) use interface::{
+    cache_stats,
+    clear_cache,
+    prune_cache,
+    read_json,
+    warn_on_write_failure,
+    write_json,
+    ClusterStats,
+    TransactionCache,
+};
@@ -0,0 +1,334 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use solana_clap_utils::input_validators::normalize_to_url_if_moniker;
+use solana_cli_config::{load_config_file, Config, CONFIG_FILE};
+use solana_sdk::commitment_config::CommitmentConfig;
+use tracing::{debug, instrument, trace, warn};
+
+use crate::adapters::gateways::signals::InterruptFlag;
+
+const DEFAULT_RPC_URL: &str = "https://api.devnet.solana.com";
+
+/// The public RPC nodes allow 100 requests per 10 seconds from each IP.
+const DEFAULT_REQUESTS_PER_SECOND: u32 = 10;
+const DEFAULT_CONCURRENCY: usize = 10;
+
+/// Checkpoints live beside the cache, but are kept even when it is disabled.
+const CHECKPOINTS_DIR: &str = "checkpoints";
+
+/// Caller-supplied settings for a provenance lookup.  Every RPC setting is
+/// optional; anything left unset falls back, in order, to the solc config
+/// file, the Solana CLI config file, and finally the built-in defaults.
+#[derive(Debug, Default, Clone)]
+pub struct LookupOptions {
+    /// RPC node URL or cluster moniker (`m`, `d`, `t`, `l`, or their full
+    /// names).
+    pub url: Option<String>,
+
+    /// Commitment level for RPC queries, e.g. `confirmed` or `finalized`.
+    pub commitment: Option<String>,
+
+    /// Timeout in seconds for each request to the RPC node.
+    pub timeout: Option<u64>,
+
+    /// Path to a solc config file.  Defaults to `~/.config/solc/config.yml`.
+    pub config_file: Option<String>,
+
+    /// Budget of requests per second sent to the RPC node.
+    pub requests_per_second: Option<u32>,
+
+    /// Maximum number of requests in flight to the RPC node at once.
+    pub concurrency: Option<usize>,
+
+    /// Fail instead of returning a result searched from a history with any
+    /// transaction details that could not be retrieved.
+    pub strict: bool,
+
+    /// Directory of the local transaction cache.  Defaults to `solc` in the
+    /// platform's cache directory, e.g. `~/.cache/solc`.
+    pub cache_dir: Option<String>,
+
+    /// Neither read from nor write to the local transaction cache.
+    pub no_cache: bool,
+
+    /// Answer from the local transaction cache alone, without contacting the
+    /// RPC node.
+    pub offline: bool,
+
+    /// Continue an interrupted history search from its checkpoint.
+    pub resume: bool,
+
+    /// File to record every JSON-RPC exchange with the RPC node to.
+    pub record_file: Option<String>,
+
+    /// File of recorded JSON-RPC exchanges to serve instead of contacting
+    /// the RPC node.
+    pub replay_file: Option<String>,
+
+    /// Stops history scans at their next checkpoint once requested.  Nothing
+    /// requests it unless the caller does, e.g. from a Ctrl-C handler.
+    pub interrupt: InterruptFlag,
+}
+
+/// The fully resolved settings used to construct the RPC gateway.
+#[derive(Debug, Clone)]
+pub(crate) struct RpcSettings {
+    pub json_rpc_url: String,
+    pub commitment: CommitmentConfig,
+    pub timeout: Option<Duration>,
+    pub requests_per_second: u32,
+    pub concurrency: usize,
+
+    /// `None` when the cache is disabled.
+    pub cache_dir: Option<PathBuf>,
+    pub offline: bool,
+
+    /// `None` when no directory is available for checkpoints.
+    pub checkpoint_dir: Option<PathBuf>,
+    pub resume: bool,
+
+    pub record_file: Option<PathBuf>,
+    pub replay_file: Option<PathBuf>,
+}
+
+/// Layout of the optional solc config file.  All keys are optional.
+#[derive(Debug, Default, Deserialize)]
+struct SolcConfig {
+    json_rpc_url: Option<String>,
+    commitment: Option<String>,
+    timeout: Option<u64>,
+    rps: Option<u32>,
+    concurrency: Option<usize>,
+    cache_dir: Option<String>,
+}
+
+#[instrument]
+pub(crate) fn resolve_rpc_settings(options: &LookupOptions) -> Result<RpcSettings> {
+    let solc_config = load_solc_config(options.config_file.as_deref())?;
+    let solana_config = load_solana_cli_config();
+
+    let url_or_moniker = first_setting(
+        "json_rpc_url",
+        [
+            ("command line or environment", options.url.clone()),
+            ("solc config", solc_config.json_rpc_url),
+            (
+                "Solana CLI config",
+                solana_config.as_ref().map(|c| c.json_rpc_url.clone()),
+            ),
+        ],
+    )
+    .unwrap_or_else(|| DEFAULT_RPC_URL.to_string());
+    let json_rpc_url = normalize_to_url_if_moniker(url_or_moniker);
+
+    let commitment = match first_setting(
+        "commitment",
+        [
+            ("command line or environment", options.commitment.clone()),
+            ("solc config", solc_config.commitment),
+            (
+                "Solana CLI config",
+                solana_config.as_ref().map(|c| c.commitment.clone()),
+            ),
+        ],
+    ) {
+        Some(commitment) => CommitmentConfig::from_str(&commitment).map_err(|e| {
+            anyhow!(
+                "Failed to parse commitment level: {commitment} .  Expected one of `processed`, \
+                 `confirmed`, or `finalized`.  {e}"
+            )
+        })?,
+        None => CommitmentConfig::finalized(),
+    };
+    let commitment = if commitment.is_at_least_confirmed() {
+        commitment
+    } else {
+        warn!(
+            "The RPC node does not serve transaction history at `processed` commitment.  Using \
+             `confirmed` instead."
+        );
+        CommitmentConfig::confirmed()
+    };
+
+    let timeout = options
+        .timeout
+        .or(solc_config.timeout)
+        .map(Duration::from_secs);
+
+    let requests_per_second = options
+        .requests_per_second
+        .or(solc_config.rps)
+        .unwrap_or(DEFAULT_REQUESTS_PER_SECOND);
+    let concurrency = options
+        .concurrency
+        .or(solc_config.concurrency)
+        .unwrap_or(DEFAULT_CONCURRENCY);
+    if requests_per_second == 0 || concurrency == 0 {
+        return Err(anyhow!(
+            "The requests per second and concurrency limits must both be at least 1, but got \
+             {requests_per_second} and {concurrency}."
+        ));
+    }
+
+    let state_dir = cache_dir_setting(options, solc_config.cache_dir);
+    let recording = options.record_file.is_some();
+    let recording_or_replaying = recording || options.replay_file.is_some();
+    if recording_or_replaying && options.offline {
+        return Err(anyhow!(
+            "Offline mode answers from the cache, which recording and replaying bypass, so they \
+             cannot be combined."
+        ));
+    }
+    let cache_dir = if recording_or_replaying {
+        // Requests answered from the cache would be missing from a recording,
+        // and a replay should not leave anything behind.
+        debug!("Not using the cache while recording or replaying.");
+        None
+    } else if options.no_cache {
+        if options.offline {
+            return Err(anyhow!(
+                "Offline mode answers from the cache alone, so it cannot be combined with \
+                 disabling the cache."
+            ));
+        }
+        None
+    } else if options.offline || commitment == CommitmentConfig::finalized() {
+        // Only finalized data can never be rolled back, so anything less would
+        // risk caching transactions that later vanish from the ledger.
+        Some(state_dir.clone().ok_or_else(missing_cache_dir_error)?)
+    } else {
+        warn!(
+            "Not using the cache, as only finalized data is cached and the commitment is \
+             `confirmed`.  Use `--commitment finalized` to look up from the cache."
+        );
+        None
+    };
+
+    if recording && options.resume {
+        return Err(anyhow!(
+            "A recording must hold every request of the lookup, so it cannot resume a checkpoint."
+        ));
+    }
+    // For the same reasons as the cache, a recording keeps no checkpoints,
+    // and a replay only uses one when asked to resume it.
+    let checkpoint_dir = state_dir
+        .filter(|_| !recording_or_replaying || options.resume)
+        .map(|dir| dir.join(CHECKPOINTS_DIR));
+    if options.resume && checkpoint_dir.is_none() {
+        return Err(missing_cache_dir_error());
+    }
+
+    debug!(
+        "Resolved RPC node URL: {json_rpc_url}, commitment: {commitment:?}, timeout: {timeout:?}, \
+         requests per second: {requests_per_second}, concurrency: {concurrency}, cache: \
+         {cache_dir:?}, offline: {}, checkpoints: {checkpoint_dir:?}",
+        options.offline
+    );
+
+    Ok(RpcSettings {
+        json_rpc_url,
+        commitment,
+        timeout,
+        requests_per_second,
+        concurrency,
+        cache_dir,
+        offline: options.offline,
+        checkpoint_dir,
+        resume: options.resume,
+        record_file: options.record_file.as_ref().map(PathBuf::from),
+        replay_file: options.replay_file.as_ref().map(PathBuf::from),
+    })
+}
+
+/// Resolves the cache directory alone, for managing the cache without
+/// looking anything up.
+#[instrument]
+pub(crate) fn resolve_cache_dir(options: &LookupOptions) -> Result<PathBuf> {
+    let solc_config = load_solc_config(options.config_file.as_deref())?;
+
+    cache_dir_setting(options, solc_config.cache_dir).ok_or_else(missing_cache_dir_error)
+}
+
+fn cache_dir_setting(options: &LookupOptions, solc_config: Option<String>) -> Option<PathBuf> {
+    first_setting(
+        "cache_dir",
+        [
+            ("command line or environment", options.cache_dir.clone()),
+            ("solc config", solc_config),
+        ],
+    )
+    .map(PathBuf::from)
+    .or_else(default_cache_dir)
+}
+
+fn missing_cache_dir_error() -> anyhow::Error {
+    anyhow!(
+        "Failed to find a cache directory for this platform.  Set one with `--cache-dir` or \
+         `SOLC_CACHE_DIR`."
+    )
+}
+
+fn first_setting<const N: usize>(
+    name: &str,
+    settings: [(&str, Option<String>); N],
+) -> Option<String> {
+    settings
+        .into_iter()
+        .find(|(_, value)| value.as_deref().is_some_and(|v| !v.trim().is_empty()))
+        .and_then(|(source, value)| {
+            trace!("Using {name} from {source}.");
+            value
+        })
+}
+
+#[instrument]
+fn load_solc_config(config_file: Option<&str>) -> Result<SolcConfig> {
+    match config_file {
+        Some(path) => load_config_file(path).map_err(|e| {
+            anyhow!(
+                "Failed to load the solc config file at {path} .  Check that the file exists and \
+                 is valid YAML.  {e}"
+            )
+        }),
+        None => {
+            let Some(path) = default_solc_config_path().filter(|path| path.exists()) else {
+                trace!("No solc config file found at the default location.");
+                return Ok(SolcConfig::default());
+            };
+
+            load_config_file(&path).map_err(|e| {
+                anyhow!(
+                    "Failed to load the solc config file at {} .  Check that the file is valid \
+                     YAML, or remove it to fall back to the Solana CLI config.  {e}",
+                    path.display()
+                )
+            })
+        }
+    }
+}
+
+#[instrument]
+fn load_solana_cli_config() -> Option<Config> {
+    let path = CONFIG_FILE.as_ref()?;
+
+    match Config::load(path) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            trace!("No usable Solana CLI config file at {path}: {e}");
+            None
+        }
+    }
+}
+
+fn default_cache_dir() -> Option<PathBuf> {
+    dirs_next::cache_dir().map(|path| path.join("solc"))
+}
+
+fn default_solc_config_path() -> Option<PathBuf> {
+    dirs_next::home_dir().map(|mut path| {
+        path.extend([".config", "solc", "config.yml"]);
+        path
+    })
+}
@@ -0,0 +1,4 @@
+pub mod interface;
+
+pub use interface::LookupOptions;
+pub(crate) use interface::{resolve_cache_dir, resolve_rpc_settings};
@@ -1,2 +1,5 @@
+pub mod cache;
+pub mod config;
+pub mod signals;
 pub mod solana;
 pub mod telemetry;
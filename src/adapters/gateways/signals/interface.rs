@@ -0,0 +1,54 @@
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tracing::{debug, instrument, warn};
+
+/// The conventional exit code for a process ended by SIGINT.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// A request for the lookups sharing this flag to stop at their next
+/// checkpoint, so their progress is saved and a partial result returned.
+/// Clones share the same flag.  Once requested, it stays requested until
+/// cleared, so a caller that goes on to run more lookups clears it first.
+#[derive(Debug, Default, Clone)]
+pub struct InterruptFlag(Arc<AtomicBool>);
+
+impl InterruptFlag {
+    pub fn request(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Turns the first Ctrl-C into a request on the flag, and exits the process
+/// immediately on a second.  Only the `solc` binary installs it; the library
+/// never touches the process's signal handling.
+#[instrument]
+pub fn install_interrupt_handler(interrupt: InterruptFlag) {
+    let installed = ctrlc::set_handler(move || {
+        if interrupt.0.swap(true, Ordering::SeqCst) {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+
+        warn!(
+            "Interrupted.  Saving progress after the transactions already in flight; press Ctrl-C \
+             again to exit immediately."
+        );
+    });
+
+    if let Err(e) = installed {
+        debug!("Keeping the existing interrupt handler: {e}");
+    }
+}
@@ -0,0 +1,3 @@
+pub mod interface;
+
+pub use interface::{install_interrupt_handler, InterruptFlag};
@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use tracing::{debug, info, instrument, warn};
+
+use super::pipeline::HistoryScan;
+use crate::adapters::gateways::cache::{read_json, write_json};
+
+/// The history scans that can be checkpointed, each with its own file per
+/// program since their progress runs in opposite directions.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum ScanKind {
+    Inception,
+    Timeline,
+}
+
+impl ScanKind {
+    fn label(self) -> &'static str {
+        match self {
+            ScanKind::Inception => "inception",
+            ScanKind::Timeline => "timeline",
+        }
+    }
+}
+
+/// The saved progress of a history scan, along with what it was scanning,
+/// so it is never resumed against a different history.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Checkpoint<S, R> {
+    rpc_url: String,
+    programdata_address: String,
+    pub scan: S,
+    pub reducer: R,
+}
+
+/// Saves the progress of history scans to one file per program and scan
+/// kind, and loads it back when resuming.
+pub(super) struct Checkpoints {
+    dir: PathBuf,
+    resume: bool,
+}
+
+impl Checkpoints {
+    pub fn new(dir: &Path, resume: bool) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            resume,
+        }
+    }
+
+    /// Loads the checkpoint of an earlier interrupted scan when resuming.
+    #[instrument(skip(self))]
+    pub fn load<R: DeserializeOwned>(
+        &self,
+        kind: ScanKind,
+        rpc_url: &str,
+        program_id: &Pubkey,
+        programdata_address: &Pubkey,
+    ) -> Result<Option<Checkpoint<HistoryScan, R>>> {
+        let path = self.path(kind, program_id);
+        if !self.resume {
+            if path.exists() {
+                info!(
+                    "Starting over, replacing the checkpoint of an interrupted lookup at {} .  \
+                     Pass `--resume` to continue it instead.",
+                    path.display()
+                );
+            }
+            return Ok(None);
+        }
+
+        let checkpoint: Option<Checkpoint<HistoryScan, R>> = read_json(&path).map_err(|e| {
+            anyhow!(
+                "Failed to read the checkpoint at {} .  Run again without `--resume` to start \
+                 over.  {e}",
+                path.display()
+            )
+        })?;
+        let Some(checkpoint) = checkpoint else {
+            info!(
+                "No checkpoint found for program_id: {program_id}, so starting from the beginning."
+            );
+            return Ok(None);
+        };
+
+        if checkpoint.rpc_url != rpc_url
+            || checkpoint.programdata_address != programdata_address.to_string()
+        {
+            return Err(anyhow!(
+                "The checkpoint at {} was saved by a lookup of {} against {}, so it cannot be \
+                 resumed against {rpc_url} .  Run again with the original URL, or without \
+                 `--resume` to start over.",
+                path.display(),
+                checkpoint.programdata_address,
+                checkpoint.rpc_url
+            ));
+        }
+        info!(
+            "Resuming the lookup of program_id: {program_id} after {} transactions.",
+            checkpoint.scan.searched
+        );
+
+        Ok(Some(checkpoint))
+    }
+
+    /// Saves the progress of a scan.  A failed save only loses the chance
+    /// to resume, so it is logged rather than failing the scan.
+    #[instrument(skip_all)]
+    pub fn save<R: Serialize>(
+        &self,
+        kind: ScanKind,
+        rpc_url: &str,
+        program_id: &Pubkey,
+        programdata_address: &Pubkey,
+        scan: &HistoryScan,
+        reducer: &R,
+    ) {
+        let path = self.path(kind, program_id);
+        debug!(
+            "Saving a checkpoint after {} transactions to {}",
+            scan.searched,
+            path.display()
+        );
+
+        let checkpoint = Checkpoint {
+            rpc_url: rpc_url.to_string(),
+            programdata_address: programdata_address.to_string(),
+            scan,
+            reducer,
+        };
+        if let Err(e) = write_json(&path, &checkpoint) {
+            warn!(
+                "Failed to save a checkpoint to {} , so this lookup cannot be resumed: {e}",
+                path.display()
+            );
+        }
+    }
+
+    /// Removes the checkpoint once its scan has completed.
+    #[instrument(skip(self))]
+    pub fn remove(&self, kind: ScanKind, program_id: &Pubkey) {
+        match fs::remove_file(self.path(kind, program_id)) {
+            Ok(()) => debug!("Removed the checkpoint of the completed scan."),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => debug!("Failed to remove the checkpoint of the completed scan: {e}"),
+        }
+    }
+
+    fn path(&self, kind: ScanKind, program_id: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{}-{program_id}.json", kind.label()))
+    }
+}
@@ -0,0 +1,516 @@
+use std::{fmt, iter, str::FromStr};
+
+use chrono::prelude::*;
+use solana_sdk::{bs58, pubkey::Pubkey, transaction::VersionedTransaction};
+use solana_sdk_ids::{bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable, loader_v4};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta,
+    UiInnerInstructions,
+    UiInstruction,
+    UiLoadedAddresses,
+    UiTransactionStatusMeta,
+};
+use tracing::{debug, instrument, trace};
+
+use crate::entities::{Deployment, DeploymentKind, DeploymentOutcome};
+
+/// The stack height of the instructions of the transaction itself, under
+/// which each cross-program invocation is nested one level deeper.
+const TOP_LEVEL_STACK_HEIGHT: u32 = 1;
+
+/// The loaders that can own a deployed program.  Each has its own
+/// instruction set, so a program's history is classified with the decoder of
+/// the loader that owns it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProgramLoader {
+    /// `BPFLoader1111`, which deployed the earliest programs.
+    BpfLoaderDeprecated,
+
+    /// `BPFLoader2111`, which deploys programs that can never be upgraded.
+    BpfLoader,
+    BpfLoaderUpgradeable,
+    LoaderV4,
+}
+
+impl ProgramLoader {
+    pub fn from_id(id: &Pubkey) -> Option<Self> {
+        if *id == bpf_loader_deprecated::id() {
+            Some(Self::BpfLoaderDeprecated)
+        } else if *id == bpf_loader::id() {
+            Some(Self::BpfLoader)
+        } else if *id == bpf_loader_upgradeable::id() {
+            Some(Self::BpfLoaderUpgradeable)
+        } else if *id == loader_v4::id() {
+            Some(Self::LoaderV4)
+        } else {
+            None
+        }
+    }
+
+    /// Programs migrated to `LoaderV4` keep the `BPFLoaderUpgradeab1e`
+    /// history from before the migration, so both are classified for them.
+    fn classifies(self, instruction_loader: ProgramLoader) -> bool {
+        self == instruction_loader
+            || (self == Self::LoaderV4 && instruction_loader == Self::BpfLoaderUpgradeable)
+    }
+}
+
+impl fmt::Display for ProgramLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ProgramLoader::BpfLoaderDeprecated => "BPFLoader1111",
+            ProgramLoader::BpfLoader => "BPFLoader2111",
+            ProgramLoader::BpfLoaderUpgradeable => "BPFLoaderUpgradeab1e",
+            ProgramLoader::LoaderV4 => "LoaderV4",
+        };
+
+        f.write_str(label)
+    }
+}
+
+/// The variants of the `LoaderInstruction` shared by `BPFLoader1111` and
+/// `BPFLoader2111`, in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LegacyLoaderInstructionKind {
+    Write,
+    Finalize,
+}
+
+impl LegacyLoaderInstructionKind {
+    fn from_instruction_data(data: &[u8]) -> Option<Self> {
+        match instruction_tag(data)? {
+            0 => Some(Self::Write),
+            1 => Some(Self::Finalize),
+            _ => None,
+        }
+    }
+
+    /// A legacy program is written in place and then finalized, which
+    /// deploys it for good.
+    fn deployment_kind(self) -> Option<DeploymentKind> {
+        match self {
+            Self::Write => None,
+            Self::Finalize => Some(DeploymentKind::InitialDeploy),
+        }
+    }
+}
+
+/// The variants of `UpgradeableLoaderInstruction`, in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpgradeableLoaderInstructionKind {
+    InitializeBuffer,
+    Write,
+    DeployWithMaxDataLen,
+    Upgrade,
+    SetAuthority,
+    Close,
+    ExtendProgram,
+    SetAuthorityChecked,
+    Migrate,
+}
+
+impl UpgradeableLoaderInstructionKind {
+    /// Decoding only the tag keeps classification in step with variants
+    /// newer than the linked SDK, such as `Migrate`.
+    fn from_instruction_data(data: &[u8]) -> Option<Self> {
+        match instruction_tag(data)? {
+            0 => Some(Self::InitializeBuffer),
+            1 => Some(Self::Write),
+            2 => Some(Self::DeployWithMaxDataLen),
+            3 => Some(Self::Upgrade),
+            4 => Some(Self::SetAuthority),
+            5 => Some(Self::Close),
+            6 => Some(Self::ExtendProgram),
+            7 => Some(Self::SetAuthorityChecked),
+            8 => Some(Self::Migrate),
+            _ => None,
+        }
+    }
+
+    /// Buffer management instructions never act on a deployed program, so
+    /// they have no place in its deployment history.
+    fn deployment_kind(self) -> Option<DeploymentKind> {
+        match self {
+            Self::InitializeBuffer | Self::Write => None,
+            Self::DeployWithMaxDataLen => Some(DeploymentKind::InitialDeploy),
+            Self::Upgrade => Some(DeploymentKind::Upgrade),
+            Self::SetAuthority | Self::SetAuthorityChecked => Some(DeploymentKind::SetAuthority),
+            Self::Close => Some(DeploymentKind::Close),
+            Self::ExtendProgram => Some(DeploymentKind::ExtendProgram),
+            Self::Migrate => Some(DeploymentKind::Migrate),
+        }
+    }
+}
+
+/// The variants of `LoaderV4Instruction` as the on-chain loader declares
+/// them, which the linked SDK predates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoaderV4InstructionKind {
+    Write,
+    Copy,
+    SetProgramLength { new_size: u32 },
+    Deploy,
+    Retract,
+    TransferAuthority,
+    Finalize,
+}
+
+impl LoaderV4InstructionKind {
+    fn from_instruction_data(data: &[u8]) -> Option<Self> {
+        match instruction_tag(data)? {
+            0 => Some(Self::Write),
+            1 => Some(Self::Copy),
+            2 => {
+                let new_size = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?);
+                Some(Self::SetProgramLength { new_size })
+            }
+            3 => Some(Self::Deploy),
+            4 => Some(Self::Retract),
+            5 => Some(Self::TransferAuthority),
+            6 => Some(Self::Finalize),
+            _ => None,
+        }
+    }
+
+    /// The same `Deploy` instruction both deploys a program and redeploys
+    /// it after a retract, so every deploy is classified as an initial one
+    /// and the timeline relabels all but the first as upgrades.  Setting the
+    /// length of a program to zero closes it; any other length only
+    /// prepares it for the next deploy.
+    fn deployment_kind(self) -> Option<DeploymentKind> {
+        match self {
+            Self::Write | Self::Copy => None,
+            Self::SetProgramLength { new_size: 0 } => Some(DeploymentKind::Close),
+            Self::SetProgramLength { .. } => None,
+            Self::Deploy => Some(DeploymentKind::InitialDeploy),
+            Self::Retract => Some(DeploymentKind::Retract),
+            Self::TransferAuthority => Some(DeploymentKind::SetAuthority),
+            Self::Finalize => Some(DeploymentKind::Finalize),
+        }
+    }
+}
+
+/// Loader instruction data is the bincode encoding of the loader's
+/// instruction enum, which leads with the variant index as a little-endian
+/// `u32`.
+fn instruction_tag(data: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoaderInstructionKind {
+    Legacy(LegacyLoaderInstructionKind),
+    Upgradeable(UpgradeableLoaderInstructionKind),
+    V4(LoaderV4InstructionKind),
+}
+
+impl LoaderInstructionKind {
+    fn from_instruction_data(loader: ProgramLoader, data: &[u8]) -> Option<Self> {
+        match loader {
+            ProgramLoader::BpfLoaderDeprecated | ProgramLoader::BpfLoader => {
+                LegacyLoaderInstructionKind::from_instruction_data(data).map(Self::Legacy)
+            }
+            ProgramLoader::BpfLoaderUpgradeable => {
+                UpgradeableLoaderInstructionKind::from_instruction_data(data).map(Self::Upgradeable)
+            }
+            ProgramLoader::LoaderV4 => {
+                LoaderV4InstructionKind::from_instruction_data(data).map(Self::V4)
+            }
+        }
+    }
+
+    fn deployment_kind(self) -> Option<DeploymentKind> {
+        match self {
+            Self::Legacy(kind) => kind.deployment_kind(),
+            Self::Upgradeable(kind) => kind.deployment_kind(),
+            Self::V4(kind) => kind.deployment_kind(),
+        }
+    }
+}
+
+/// A loader instruction with its account indexes resolved against the
+/// transaction's account keys.
+#[derive(Debug, Clone)]
+struct LoaderInstruction {
+    kind: LoaderInstructionKind,
+    accounts: Vec<Pubkey>,
+}
+
+impl LoaderInstruction {
+    fn program_account(&self) -> Option<&Pubkey> {
+        use UpgradeableLoaderInstructionKind::*;
+
+        match self.kind {
+            LoaderInstructionKind::Legacy(_) | LoaderInstructionKind::V4(_) => {
+                self.accounts.first()
+            }
+            LoaderInstructionKind::Upgradeable(kind) => match kind {
+                DeployWithMaxDataLen => self.accounts.get(2),
+                Upgrade | ExtendProgram | Migrate => self.accounts.get(1),
+                Close => self.accounts.get(3),
+                InitializeBuffer | Write | SetAuthority | SetAuthorityChecked => None,
+            },
+        }
+    }
+
+    /// Only upgradeable programs keep their code in a separate ProgramData
+    /// account.
+    fn programdata_account(&self) -> Option<&Pubkey> {
+        use UpgradeableLoaderInstructionKind::*;
+
+        match self.kind {
+            LoaderInstructionKind::Legacy(_) | LoaderInstructionKind::V4(_) => None,
+            LoaderInstructionKind::Upgradeable(kind) => match kind {
+                DeployWithMaxDataLen => self.accounts.get(1),
+                Upgrade | SetAuthority | SetAuthorityChecked | Close | ExtendProgram | Migrate => {
+                    self.accounts.first()
+                }
+                InitializeBuffer | Write => None,
+            },
+        }
+    }
+
+    /// Legacy programs are signed for by their own keypair rather than an
+    /// authority.
+    fn authority(&self) -> Option<&Pubkey> {
+        use UpgradeableLoaderInstructionKind::*;
+
+        match self.kind {
+            LoaderInstructionKind::Legacy(_) => None,
+            LoaderInstructionKind::V4(_) => self.accounts.get(1),
+            LoaderInstructionKind::Upgradeable(kind) => match kind {
+                InitializeBuffer | Write | SetAuthority | SetAuthorityChecked => {
+                    self.accounts.get(1)
+                }
+                DeployWithMaxDataLen => self.accounts.get(7),
+                Upgrade => self.accounts.get(6),
+                Close | Migrate => self.accounts.get(2),
+                ExtendProgram => None,
+            },
+        }
+    }
+
+    fn targets(&self, program_id: &Pubkey, programdata_address: &Pubkey) -> bool {
+        self.program_account() == Some(program_id)
+            || self.programdata_account() == Some(programdata_address)
+    }
+}
+
+/// Extracts every loader instruction in the transaction that acts on the
+/// given program, from both the top-level and inner instructions, in
+/// execution order.  Only the instructions of the loader that owns the
+/// program are decoded, or of every loader when the owner is unknown.
+/// Returns `None` when the transaction cannot be decoded.
+#[instrument(skip(rpc_txn))]
+pub(crate) fn classify_deployments(
+    rpc_txn: &EncodedConfirmedTransactionWithStatusMeta,
+    loader: Option<ProgramLoader>,
+    program_id: &Pubkey,
+    programdata_address: &Pubkey,
+) -> Option<Vec<Deployment>> {
+    let Some(versioned_txn) = rpc_txn.transaction.transaction.decode() else {
+        debug!(
+            "Transaction at slot {} could not be decoded, so it was not classified.",
+            rpc_txn.slot
+        );
+        return None;
+    };
+    trace!("{:?}", versioned_txn);
+
+    let meta = rpc_txn.transaction.meta.as_ref();
+    let account_keys = resolve_account_keys(&versioned_txn, meta)?;
+    let signature = versioned_txn
+        .signatures
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let fee_payer = account_keys
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let block_time = rpc_txn
+        .block_time
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+    let outcome = match meta {
+        Some(meta) => match &meta.err {
+            Some(err) => DeploymentOutcome::Failed {
+                error: err.to_string(),
+            },
+            None => DeploymentOutcome::Succeeded,
+        },
+        None => DeploymentOutcome::Failed {
+            error: "The RPC node returned no status metadata for this transaction.".to_string(),
+        },
+    };
+
+    let deployments = loader_instructions(loader, &versioned_txn, meta, &account_keys)
+        .into_iter()
+        .filter(|instruction| instruction.targets(program_id, programdata_address))
+        .filter_map(|instruction| {
+            debug!("Transaction {signature} carries {:?}", instruction.kind);
+
+            Some(Deployment {
+                kind: instruction.kind.deployment_kind()?,
+                slot: rpc_txn.slot,
+                signature: signature.clone(),
+                block_time,
+                authority: instruction.authority().map(ToString::to_string),
+                fee_payer: fee_payer.clone(),
+                outcome: outcome.clone(),
+            })
+        })
+        .collect();
+
+    Some(deployments)
+}
+
+/// Instructions in a v0 message index into the static account keys followed
+/// by the writable and then the readonly addresses loaded from address
+/// lookup tables, which the RPC node reports in the status metadata.
+fn resolve_account_keys(
+    versioned_txn: &VersionedTransaction,
+    meta: Option<&UiTransactionStatusMeta>,
+) -> Option<Vec<Pubkey>> {
+    let mut account_keys = versioned_txn.message.static_account_keys().to_vec();
+
+    let loaded_addresses =
+        meta.and_then(|meta| Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref()));
+    if let Some(loaded_addresses) = loaded_addresses {
+        for address in loaded_addresses
+            .writable
+            .iter()
+            .chain(loaded_addresses.readonly.iter())
+        {
+            match Pubkey::from_str(address) {
+                Ok(pubkey) => account_keys.push(pubkey),
+                Err(e) => {
+                    debug!("Failed to parse loaded address {address}: {e}");
+                    return None;
+                }
+            }
+        }
+    }
+
+    Some(account_keys)
+}
+
+/// Decodes the loader instructions of the transaction, interleaving each
+/// top-level instruction with the inner instructions it invoked.
+fn loader_instructions(
+    loader: Option<ProgramLoader>,
+    versioned_txn: &VersionedTransaction,
+    meta: Option<&UiTransactionStatusMeta>,
+    account_keys: &[Pubkey],
+) -> Vec<LoaderInstruction> {
+    let inner_instructions = meta
+        .and_then(|meta| {
+            Option::<&Vec<UiInnerInstructions>>::from(meta.inner_instructions.as_ref())
+        })
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    versioned_txn
+        .message
+        .instructions()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, instruction)| {
+            let top_level = decode_loader_instruction(
+                loader,
+                instruction.program_id_index,
+                &instruction.accounts,
+                &instruction.data,
+                account_keys,
+            );
+
+            let inner = inner_instructions
+                .iter()
+                .filter(move |inner| usize::from(inner.index) == index)
+                .flat_map(|inner| inner.instructions.iter())
+                .map(|instruction| match instruction {
+                    UiInstruction::Compiled(compiled) => {
+                        let decoded =
+                            bs58::decode(&compiled.data)
+                                .into_vec()
+                                .ok()
+                                .and_then(|data| {
+                                    decode_loader_instruction(
+                                        loader,
+                                        compiled.program_id_index,
+                                        &compiled.accounts,
+                                        &data,
+                                        account_keys,
+                                    )
+                                });
+                        (compiled.stack_height, decoded)
+                    }
+                    UiInstruction::Parsed(_) => (None, None),
+                });
+
+            skip_migration_steps(iter::once((Some(TOP_LEVEL_STACK_HEIGHT), top_level)).chain(inner))
+        })
+        .collect()
+}
+
+/// `Migrate` moves a program to `LoaderV4` by invoking its
+/// `SetProgramLength`, `Copy`, `Deploy`, and then `TransferAuthority` or
+/// `Finalize` on the program account, which are steps of the migration
+/// rather than deployments of their own.  They are the `LoaderV4`
+/// instructions nested below the `Migrate`, or every one after it when the
+/// RPC node reports no stack heights.
+fn skip_migration_steps(
+    instructions: impl Iterator<Item = (Option<u32>, Option<LoaderInstruction>)>,
+) -> Vec<LoaderInstruction> {
+    let mut migrating_at = None;
+    let mut kept = Vec::new();
+
+    for (stack_height, instruction) in instructions {
+        if let Some(migrate_height) = migrating_at {
+            let nested = match (migrate_height, stack_height) {
+                (Some(migrate_height), Some(stack_height)) => stack_height > migrate_height,
+                _ => true,
+            };
+            if !nested {
+                migrating_at = None;
+            }
+        }
+        let Some(instruction) = instruction else {
+            continue;
+        };
+
+        match instruction.kind {
+            LoaderInstructionKind::Upgradeable(UpgradeableLoaderInstructionKind::Migrate) => {
+                migrating_at = Some(stack_height);
+            }
+            LoaderInstructionKind::V4(kind) if migrating_at.is_some() => {
+                trace!("Skipping {kind:?}, a step of the migration to LoaderV4");
+                continue;
+            }
+            _ => {}
+        }
+        kept.push(instruction);
+    }
+
+    kept
+}
+
+fn decode_loader_instruction(
+    loader: Option<ProgramLoader>,
+    program_id_index: u8,
+    accounts: &[u8],
+    data: &[u8],
+    account_keys: &[Pubkey],
+) -> Option<LoaderInstruction> {
+    let program = account_keys.get(usize::from(program_id_index))?;
+    let instruction_loader = ProgramLoader::from_id(program).filter(|instruction_loader| {
+        loader.is_none_or(|loader| loader.classifies(*instruction_loader))
+    })?;
+
+    let kind = LoaderInstructionKind::from_instruction_data(instruction_loader, data)?;
+    let accounts = accounts
+        .iter()
+        .map(|index| account_keys.get(usize::from(*index)).copied())
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(LoaderInstruction { kind, accounts })
+}
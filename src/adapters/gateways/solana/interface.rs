@@ -1,247 +1,831 @@
-use std::str::FromStr;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+    thread,
+    time::Duration,
+};
 
 use anyhow::{anyhow, Result};
 use chrono::prelude::*;
-use rayon::prelude::*;
+use rand::Rng;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde::{de::DeserializeOwned, Serialize};
 use solana_client::{
-    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
-    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+    client_error::{reqwest::StatusCode, ClientError, ClientErrorKind},
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient, RpcClientConfig},
+    rpc_config::RpcTransactionConfig,
+    rpc_sender::RpcSender,
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
-use solana_transaction_status::{
-    EncodedConfirmedTransactionWithStatusMeta,
-    EncodedTransaction,
-    UiMessage,
-    UiParsedMessage,
-    UiTransaction,
-    UiTransactionEncoding,
+use solana_loader_v3_interface::{get_program_data_address, state::UpgradeableLoaderState};
+use solana_sdk::{
+    account::Account,
+    account_utils::StateMut,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use tracing::{debug, info, instrument, warn};
+
+use super::{
+    checkpoint::{Checkpoints, ScanKind},
+    classification::{classify_deployments, ProgramLoader},
+    pipeline::{
+        ensure_not_stale,
+        resume_after,
+        run_pipeline,
+        HistoryPages,
+        HistoryReducer,
+        HistoryScan,
+        InceptionSearch,
+        OldestFirstPages,
+        ScanControl,
+        SignaturePage,
+        TimelineBuilder,
+        DEFAULT_SERVER_SIDE_BATCH_LIMIT,
+    },
+    preflight::{preflight, ProgramAccount},
+};
+use crate::{
+    adapters::gateways::{
+        cache::{warn_on_write_failure, TransactionCache},
+        signals::InterruptFlag,
+    },
+    entities::{
+        Deployment,
+        DeploymentKind,
+        DeploymentTimeline,
+        LastDeployment,
+        ProgramId,
+        ProvenanceReport,
+        SolceptionError,
+    },
+    use_cases::SolanaQueries,
 };
-use tracing::{debug, info, instrument, trace, warn};
-
-use crate::use_cases::SolanaQueries;
 
-const DEFAULT_SERVER_SIDE_BATCH_LIMIT: usize = 1000;
+const MAX_FETCH_ATTEMPTS: u32 = 5;
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(250);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
 
 pub(crate) struct SolanaRpc {
     rpc_client: RpcClient,
+
+    /// Runs the parallel detail fetches, sized to the sender's in-flight
+    /// limit so that limit is reachable regardless of the number of cores.
+    thread_pool: ThreadPool,
+
+    /// `None` when the cache is disabled.
+    cache_dir: Option<PathBuf>,
+
+    /// Opened on first use, so invalid input fails before asking the RPC
+    /// node for its genesis hash.
+    cache: OnceLock<TransactionCache>,
+
+    /// Answer from the cache alone, never contacting the RPC node.
+    offline: bool,
+
+    checkpoints: Option<Checkpoints>,
+
+    /// Stops history scans at their next checkpoint once requested.
+    interrupt: InterruptFlag,
 }
 
 impl SolanaRpc {
     #[instrument(skip(rpc_client))]
-    fn new(rpc_client: RpcClient) -> Self {
-        Self { rpc_client }
+    fn new(rpc_client: RpcClient, concurrency: usize) -> Result<Self> {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(concurrency)
+            .thread_name(|index| format!("solcFetch{index}"))
+            .build()?;
+
+        Ok(Self {
+            rpc_client,
+            thread_pool,
+            cache_dir: None,
+            cache: OnceLock::new(),
+            offline: false,
+            checkpoints: None,
+            interrupt: InterruptFlag::default(),
+        })
     }
 
-    #[instrument]
-    pub fn new_with_url(rpc_url: &str) -> Self {
-        let rpc_client = RpcClient::new(rpc_url.to_string());
+    #[instrument(skip(sender))]
+    pub fn new_with_sender<T: RpcSender + Send + Sync + 'static>(
+        sender: T,
+        commitment: CommitmentConfig,
+        concurrency: usize,
+    ) -> Result<Self> {
+        let rpc_client =
+            RpcClient::new_sender(sender, RpcClientConfig::with_commitment(commitment));
 
-        Self::new(rpc_client)
+        Self::new(rpc_client, concurrency)
     }
 
-    #[instrument]
-    pub fn new_with_timeout(rpc_url: &str, timeout: u64) -> Self {
-        let rpc_client = RpcClient::new_with_timeout(
-            rpc_url.to_string(),
-            std::time::Duration::from_secs(timeout),
-        );
+    /// The RPC node queried, or the fixture file replayed in its place.
+    pub fn url(&self) -> String {
+        self.rpc_client.url()
+    }
 
-        Self::new(rpc_client)
+    /// Reads and writes signature pages and transaction details through the
+    /// cache for the RPC node's cluster.  Offline, the cache is the only
+    /// source of data.
+    #[instrument(skip(self))]
+    pub fn attach_cache(&mut self, cache_dir: &Path, offline: bool) {
+        self.cache_dir = Some(cache_dir.to_path_buf());
+        self.offline = offline;
     }
-}
 
-impl SolanaQueries for SolanaRpc {
+    /// Stops history scans at their next checkpoint once the flag is
+    /// requested.
     #[instrument(skip(self))]
-    fn get_first_deployed_slot_timestamp(&self, program_id: &str) -> Result<DateTime<Utc>> {
-        let program_id = Pubkey::from_str(program_id).map_err(|e| {
-            anyhow!(
-                "Failed to parse program_id: {} .  Most likely the provided value is not a base \
-                 58 public key.  Check the input against a blockchain explorer, and if it is \
-                 valid, check the Git blame for the Solana Rust SDK to see if related changes \
-                 were made to the Pubkey object's parser.",
-                e
-            )
+    pub fn attach_interrupt(&mut self, interrupt: InterruptFlag) {
+        self.interrupt = interrupt;
+    }
+
+    /// Saves the progress of history scans to checkpoints in the directory,
+    /// resuming from them when asked.
+    #[instrument(skip(self))]
+    pub fn attach_checkpoints(&mut self, checkpoint_dir: &Path, resume: bool) {
+        self.checkpoints = Some(Checkpoints::new(checkpoint_dir, resume));
+    }
+
+    /// Returns the cache for the RPC node's cluster, opening it on first use.
+    fn open_cache(&self) -> Result<Option<&TransactionCache>> {
+        let Some(cache_dir) = &self.cache_dir else {
+            return Ok(None);
+        };
+        if let Some(cache) = self.cache.get() {
+            return Ok(Some(cache));
+        }
+
+        let rpc_url = self.rpc_client.url();
+        let cache = TransactionCache::open_for_cluster(cache_dir, &rpc_url, self.offline, || {
+            let genesis_hash = self.rpc_client.get_genesis_hash().map_err(rpc_error)?;
+            Ok(genesis_hash.to_string())
         })?;
 
-        let transactions = crawl_transaction_history(&self.rpc_client, &program_id)?;
+        Ok(Some(self.cache.get_or_init(|| cache)))
+    }
+
+    /// Brings the cached history of the address up to date, crawling only
+    /// the transactions newer than the newest one already cached.  The crawl
+    /// is recorded as a new run, which only counts once it completes.
+    #[instrument(skip(self, cache))]
+    fn refresh_history(&self, cache: &TransactionCache, address: &Pubkey) -> Result<()> {
+        if self.offline {
+            debug!("Offline, so using the cached history of {address} as is.");
+            return Ok(());
+        }
+
+        let history = cache.history(address)?;
+        let newest_signature = history.newest_signature();
+        let until = newest_signature.map(parse_signature).transpose()?;
         debug!(
-            "Retrieved {} transaction summaries for {}",
-            transactions.len(),
-            program_id
+            "{} transaction summaries are cached for {address}",
+            history.transaction_count()
         );
 
-        if transactions.is_empty() {
-            return Err(anyhow!(
-                "No transactions found for program_id: {program_id}"
-            ));
+        let mut run = cache.start_run(address, newest_signature)?;
+        for page in HistoryPages::new(&self.rpc_client, *address, until) {
+            run.write_page(&page?)?;
         }
 
+        run.complete()
+    }
+
+    /// Streams the history of the given pages through the fetch and classify
+    /// stages into a reducer.  When resuming, the reducer and scan state come
+    /// from the checkpoint, and the pages are built from the scan state to
+    /// start after its last signature.  Progress is checkpointed as the scan
+    /// runs, and the checkpoint removed once it completes.
+    #[instrument(skip(self, pages))]
+    fn scan_history<P, R>(
+        &self,
+        kind: ScanKind,
+        loader: Option<ProgramLoader>,
+        program_id: &Pubkey,
+        programdata_address: &Pubkey,
+        pages: impl FnOnce(&HistoryScan) -> Result<P>,
+    ) -> Result<(R, HistoryScan)>
+    where
+        P: Iterator<Item = Result<SignaturePage>> + Send,
+        R: HistoryReducer + Default + Serialize + DeserializeOwned,
+    {
+        let rpc_url = self.rpc_client.url();
+        let resumed = match &self.checkpoints {
+            Some(checkpoints) => {
+                checkpoints.load(kind, &rpc_url, program_id, programdata_address)?
+            }
+            None => None,
+        };
+        let (mut reducer, scan) = resumed
+            .map(|checkpoint| (checkpoint.reducer, checkpoint.scan))
+            .unwrap_or_default();
+        let pages = pages(&scan)?;
+
         info!(
             "Retrieving transaction details for program_id: {program_id}.  This may take some \
              time, depending on the number of transactions and the chosen cluster RPC node's rate \
              limits."
         );
 
-        if transactions.len() > DEFAULT_SERVER_SIDE_BATCH_LIMIT {
-            warn!(
-                "The number of transactions for program_id: {program_id} exceeds 1000.  This may \
-                 take a long time to retrieve all transaction details, depending on the chosen \
-                 cluster RPC node's rate limits!"
-            );
-        }
+        let scan = run_pipeline(
+            pages,
+            self.thread_pool.current_num_threads(),
+            |window| self.fetch_window(window),
+            |txn| classify_deployments(txn, loader, program_id, programdata_address),
+            &mut reducer,
+            scan,
+            ScanControl {
+                interrupt: &self.interrupt,
+                checkpoint: |scan: &HistoryScan, reducer: &R| {
+                    if let Some(checkpoints) = &self.checkpoints {
+                        checkpoints.save(
+                            kind,
+                            &rpc_url,
+                            program_id,
+                            programdata_address,
+                            scan,
+                            reducer,
+                        );
+                    }
+                },
+            },
+        )?;
+        debug!("Scanned {} transactions for {}", scan.searched, program_id);
 
-        let txn_details = transactions
-            .par_iter()
-            .filter_map(|txn| {
-                let sig = Signature::from_str(&txn.signature).expect(
-                    "Failed to parse transaction signature taken directly from RPC response \
-                     content.  This should only occur if this module's codepath was changed or \
-                     the Solana Labs crates have changed the signature format.  Check the Git \
-                     blame for this module first and then the Solana Rust SDK to see if related \
-                     changes were made to the Signature object's parser.",
-                );
-                self.rpc_client
-                    .get_transaction(&sig, UiTransactionEncoding::JsonParsed)
-                    .ok()
-            })
-            .collect::<Vec<_>>();
+        if let Some(checkpoints) = self.checkpoints.as_ref().filter(|_| !scan.interrupted) {
+            checkpoints.remove(kind, program_id);
+        }
 
-        debug!(
-            "Retrieved {} transaction details for {}",
-            txn_details.len(),
-            program_id
+        warn_on_gaps(
+            program_id,
+            scan.searched,
+            scan.unrecoverable_signatures.len(),
+            scan.undecoded_signatures.len(),
+            self.offline,
         );
 
-        if transactions.len() != txn_details.len() {
-            warn!(
-                "Transaction details count does not match transaction summary count.  This should \
-                 not be possible, as the RPC node should be able to return a transaction detail \
-                 for every transaction summary.  Check the trace logs for actual HTTP return \
-                 codes on each attempt."
-            );
+        Ok((reducer, scan))
+    }
+
+    /// Retrieves the details of a window of transactions in parallel.
+    #[instrument(skip_all)]
+    fn fetch_window(
+        &self,
+        signatures: &[Signature],
+    ) -> Vec<Result<EncodedConfirmedTransactionWithStatusMeta, SolceptionError>> {
+        self.thread_pool.install(|| {
+            signatures
+                .par_iter()
+                .map(|sig| self.fetch_transaction(sig))
+                .collect()
+        })
+    }
+
+    /// Fetches the details of a single transaction, retrying with
+    /// exponential backoff so that a transient error or rate limit does not
+    /// silently drop a transaction from the history.
+    #[instrument(skip(self))]
+    fn fetch_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, SolceptionError> {
+        let cache_key = signature.to_string();
+        if let Some(txn) = self
+            .cache
+            .get()
+            .and_then(|cache| cache.transaction(&cache_key))
+        {
+            return Ok(txn);
+        }
+        if self.offline {
+            return Err(SolceptionError::NotCached {
+                what: format!("details of transaction {signature}"),
+            });
         }
 
-        let deployments = txn_details
-            .par_iter()
-            .filter(|txn| is_deployment(txn))
-            .collect::<Vec<_>>();
-
-        debug!("Found {} deployments for {}", deployments.len(), program_id);
-
-        let txn_block_timestamp = deployments
-            .par_iter()
-            .min_by(|a, b| {
-                a.block_time
-                    .unwrap_or_default()
-                    .cmp(&b.block_time.unwrap_or_default())
-            })
-            .expect(
-                "No deployment details found for the given program ID's history.  Most likely \
-                 this is an error in input for the address or the network, but the chosen RPC \
-                 node could be missing historical data, or network issues prevented retrieval of \
-                 some transaction details.  Check the program ID on a blockchain explorer to \
-                 verify that it is valid on the chosen network, and has a transaction history \
-                 with at least one BPFLoaderUpgradeab1e transaction.",
-            )
-            .block_time
-            .expect(
-                "No block timestamp found on the oldest deployment transaction.  This should not \
-                 be possible, as all finalized transactions have a block timestamp.  Double chack \
-                 the RPC node's historical data using an explorer, and check the Git history for \
-                 the Solana RPC library to see if there have been changes to the structures of \
-                 the transaction data.",
-            );
-        let txn_block_time = DateTime::from_timestamp(txn_block_timestamp, 0).unwrap();
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let mut attempt = 1;
+        loop {
+            match self
+                .rpc_client
+                .get_transaction_with_config(signature, config)
+            {
+                Ok(txn) => {
+                    if let Some(cache) = self.cache.get() {
+                        warn_on_write_failure(
+                            cache.store_transaction(&cache_key, &txn),
+                            "transaction details",
+                        );
+                    }
+                    return Ok(txn);
+                }
+                Err(e) if attempt < MAX_FETCH_ATTEMPTS && is_retryable(&e) => {
+                    let delay = retry_delay(attempt);
+                    debug!(
+                        "Attempt {attempt} to fetch transaction {signature} failed, retrying in \
+                         {delay:?}: {e}"
+                    );
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(e) => return Err(rpc_error(e)),
+            }
+        }
+    }
+
+    /// Resolves the loader that owns the program and the address whose
+    /// history records its deployments.  Offline, the account cannot be
+    /// read, so the derived ProgramData address is used when its history is
+    /// cached, and otherwise the program ID itself with its loader unknown.
+    #[instrument(skip(self))]
+    fn resolve_history_address(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<(Option<ProgramLoader>, Pubkey)> {
+        let Some(cache) = self.open_cache()?.filter(|_| self.offline) else {
+            return resolve_programdata_address(&self.rpc_client, program_id);
+        };
+
+        let derived_address = get_program_data_address(program_id);
+        for (loader, address) in [
+            (Some(ProgramLoader::BpfLoaderUpgradeable), derived_address),
+            (None, *program_id),
+        ] {
+            if !cache.history(&address)?.is_empty() {
+                debug!("Using the cached history of {address} for program {program_id}");
+                return Ok((loader, address));
+            }
+        }
+
+        Err(SolceptionError::NotCached {
+            what: format!("history for program_id: {program_id}"),
+        }
+        .into())
+    }
+
+    /// Fetches an account at the configured commitment, returning `None`
+    /// when no account exists at the address.
+    #[instrument(skip(self))]
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        let response = self
+            .rpc_client
+            .get_account_with_commitment(address, self.rpc_client.commitment())
+            .map_err(rpc_error)?;
 
-        Ok(txn_block_time)
+        Ok(response.value)
     }
 }
 
-#[instrument(skip(rpc_client))]
-fn crawl_transaction_history(
-    rpc_client: &RpcClient,
-    program_id: &Pubkey,
-) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
-    debug!("Retrieving transaction details for {program_id}");
+impl SolanaQueries for SolanaRpc {
+    #[instrument(skip(self))]
+    fn get_provenance_report(&self, program_id: &ProgramId) -> Result<ProvenanceReport> {
+        let program_id = program_id.pubkey();
+
+        let (loader, programdata_address) = self.resolve_history_address(&program_id)?;
+
+        let (search, scan): (InceptionSearch, _) = self.scan_history(
+            ScanKind::Inception,
+            loader,
+            &program_id,
+            &programdata_address,
+            |scan| {
+                let pages: Box<dyn Iterator<Item = Result<SignaturePage>> + Send> = match self
+                    .open_cache()?
+                {
+                    Some(cache) => {
+                        self.refresh_history(cache, &programdata_address)?;
+                        let history = cache.history(&programdata_address)?;
+                        if history.is_empty() {
+                            return Err(SolceptionError::NoDeploymentsFound {
+                                program_id: program_id.to_string(),
+                            }
+                            .into());
+                        }
+                        Box::new(history.pages_oldest_first()?)
+                    }
+                    None => {
+                        let pages = OldestFirstPages::crawl(&self.rpc_client, programdata_address)?;
+                        if pages.is_empty() {
+                            return Err(SolceptionError::NoDeploymentsFound {
+                                program_id: program_id.to_string(),
+                            }
+                            .into());
+                        }
+                        Box::new(pages)
+                    }
+                };
+
+                // The initial deploy is the oldest successful transaction
+                // that creates the ProgramData account, so the scan stops as
+                // soon as it finds one.  Failed transactions are still
+                // searched, for the attempts that preceded it.
+                Ok(resume_after(pages, scan.last_signature.clone()))
+            },
+        )?;
 
-    let mut transactions: Vec<RpcConfirmedTransactionStatusWithSignature> = Vec::new();
-    let mut before_sig_opt: Option<Signature> = None;
+        if scan.interrupted {
+            return Err(SolceptionError::Interrupted {
+                program_id: program_id.to_string(),
+                searched: scan.searched,
+                partial_result: None,
+            }
+            .into());
+        }
 
-    loop {
-        let batch = rpc_client.get_signatures_for_address_with_config(
-            program_id,
-            GetConfirmedSignaturesForAddress2Config {
-                before: before_sig_opt,
-                until: None,
-                limit: None,
-                commitment: Some(CommitmentConfig::finalized()),
+        let Some(inception) = search.inception else {
+            return Err(missing_deployment_error(
+                &program_id,
+                scan.unrecoverable_signatures.len(),
+                scan.undecoded_signatures.len(),
+                self.offline,
+            )
+            .into());
+        };
+
+        if inception.block_time.is_none() {
+            return Err(SolceptionError::HistoryTruncated {
+                program_id: program_id.to_string(),
+                reason: format!(
+                    "the RPC node has no block time for slot {} of the initial deployment",
+                    inception.slot
+                ),
+            }
+            .into());
+        }
+
+        Ok(ProvenanceReport {
+            program_id: program_id.into(),
+            programdata_address: programdata_address.into(),
+            inception,
+            failed_attempts: search.failed_attempts,
+            unrecoverable_signatures: scan.unrecoverable_signatures,
+            undecoded_signatures: scan.undecoded_signatures,
+        })
+    }
+
+    #[instrument(skip(self))]
+    fn get_deployment_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline> {
+        let program_id = program_id.pubkey();
+
+        let (loader, programdata_address) = self.resolve_history_address(&program_id)?;
+
+        let (timeline, scan): (TimelineBuilder, _) = self.scan_history(
+            ScanKind::Timeline,
+            loader,
+            &program_id,
+            &programdata_address,
+            |scan| {
+                let pages: Box<dyn Iterator<Item = Result<SignaturePage>> + Send> =
+                    match self.open_cache()? {
+                        Some(cache) => {
+                            self.refresh_history(cache, &programdata_address)?;
+                            let history = cache.history(&programdata_address)?;
+                            ensure_not_stale(scan, history.newest_signature())?;
+                            Box::new(resume_after(
+                                history.pages_newest_first()?,
+                                scan.last_signature.clone(),
+                            ))
+                        }
+                        None => {
+                            if scan.last_signature.is_some() {
+                                let newest_page =
+                                    HistoryPages::new(&self.rpc_client, programdata_address, None)
+                                        .next()
+                                        .transpose()?;
+                                let newest_signature = newest_page
+                                    .as_ref()
+                                    .and_then(|page| page.first())
+                                    .map(|txn| txn.signature.as_str());
+                                ensure_not_stale(scan, newest_signature)?;
+                            }
+                            let before = scan
+                                .last_signature
+                                .as_deref()
+                                .map(parse_signature)
+                                .transpose()?;
+                            Box::new(
+                                HistoryPages::new(&self.rpc_client, programdata_address, None)
+                                    .starting_before(before),
+                            )
+                        }
+                    };
+
+                Ok(pages)
             },
         )?;
 
-        let batch_size = batch.len();
-        before_sig_opt = batch
-            .last()
-            .map(|txn| Signature::from_str(&txn.signature).unwrap());
+        let TimelineBuilder {
+            mut deployments,
+            mut failed_attempts,
+        } = timeline;
+        if deployments.is_empty() && failed_attempts.is_empty() && !scan.interrupted {
+            return Err(missing_deployment_error(
+                &program_id,
+                scan.unrecoverable_signatures.len(),
+                scan.undecoded_signatures.len(),
+                self.offline,
+            )
+            .into());
+        }
+        deployments.sort_by_key(|deployment| deployment.slot);
+        failed_attempts.sort_by_key(|deployment| deployment.slot);
+        if loader == Some(ProgramLoader::LoaderV4) {
+            relabel_redeploys(&mut deployments);
+        }
+
+        debug!(
+            "Found {} deployments and {} failed deployment attempts for {}",
+            deployments.len(),
+            failed_attempts.len(),
+            program_id
+        );
+
+        Ok(DeploymentTimeline {
+            program_id: program_id.into(),
+            programdata_address: programdata_address.into(),
+            deployments,
+            failed_attempts,
+            unrecoverable_signatures: scan.unrecoverable_signatures,
+            undecoded_signatures: scan.undecoded_signatures,
+            interrupted_after: scan.interrupted.then_some(scan.searched),
+        })
+    }
 
-        transactions.extend(batch);
+    #[instrument(skip(self))]
+    fn get_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment> {
+        let program_id = program_id.pubkey();
 
-        if batch_size < DEFAULT_SERVER_SIDE_BATCH_LIMIT {
-            trace!("Exiting history crawl loop.");
-            break;
-        } else {
-            trace!("Continuing history crawl loop...");
+        if self.offline {
+            return Err(anyhow!(
+                "The last deployment is read from the live ProgramData account, which is not \
+                 cached.  Omit offline mode to look it up."
+            ));
         }
+
+        let programdata_address = match preflight(&self.rpc_client, &program_id)? {
+            Some(ProgramAccount {
+                loader: ProgramLoader::BpfLoaderUpgradeable,
+                history_address,
+            }) => history_address,
+            Some(ProgramAccount { loader, .. }) => {
+                return Err(SolceptionError::NotAProgram {
+                    address: program_id.to_string(),
+                    reason: format!(
+                        "it is owned by {loader} rather than BPFLoaderUpgradeab1e, so it has no \
+                         ProgramData account recording a last deployment slot.  Omit the last \
+                         deployed mode to search its full history instead"
+                    ),
+                }
+                .into());
+            }
+            None => {
+                return Err(SolceptionError::NotAProgram {
+                    address: program_id.to_string(),
+                    reason: "no account exists at this address on the chosen cluster".to_string(),
+                }
+                .into())
+            }
+        };
+
+        let Some(programdata_account) = self.fetch_account(&programdata_address)? else {
+            return Err(SolceptionError::NotAProgram {
+                address: program_id.to_string(),
+                reason: format!(
+                    "its ProgramData account {programdata_address} no longer exists, so the \
+                     program has been closed"
+                ),
+            }
+            .into());
+        };
+        let (slot, upgrade_authority_address) = match programdata_account.state() {
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot,
+                upgrade_authority_address,
+            }) => (slot, upgrade_authority_address),
+            _ => {
+                return Err(SolceptionError::DecodeFailure {
+                    what: format!("the ProgramData account {programdata_address}"),
+                    reason: format!(
+                        "it does not hold the ProgramData state for program {program_id}"
+                    ),
+                }
+                .into())
+            }
+        };
+        debug!("Program {program_id} was last deployed at slot {slot}");
+
+        let block_timestamp = self.rpc_client.get_block_time(slot).map_err(rpc_error)?;
+        let block_time = DateTime::from_timestamp(block_timestamp, 0).ok_or_else(|| {
+            SolceptionError::DecodeFailure {
+                what: format!("the block time for slot {slot}"),
+                reason: format!("{block_timestamp} is out of range"),
+            }
+        })?;
+
+        let is_inception = is_first_deployment(&self.rpc_client, &programdata_address, slot)?;
+
+        Ok(LastDeployment {
+            program_id: program_id.into(),
+            programdata_address: programdata_address.into(),
+            slot,
+            block_time,
+            upgrade_authority: upgrade_authority_address.map(|address| address.to_string()),
+            is_inception,
+        })
     }
+}
+
+pub(super) fn parse_signature(signature: &str) -> Result<Signature, SolceptionError> {
+    Signature::from_str(signature).map_err(|e| SolceptionError::DecodeFailure {
+        what: format!("the transaction signature {signature} returned by the RPC node"),
+        reason: e.to_string(),
+    })
+}
 
-    Ok(transactions)
+/// A node that answers with HTTP 429 after the client's own retries is
+/// rate limiting the lookup; every other failure to complete a request is a
+/// transport failure.
+pub(super) fn rpc_error(err: ClientError) -> SolceptionError {
+    match err.kind() {
+        ClientErrorKind::Reqwest(e) if e.status() == Some(StatusCode::TOO_MANY_REQUESTS) => {
+            SolceptionError::RateLimited {
+                reason: err.to_string(),
+            }
+        }
+        _ => SolceptionError::RpcTransport {
+            reason: err.to_string(),
+        },
+    }
 }
 
-#[instrument]
-fn is_deployment(rpc_txn: &EncodedConfirmedTransactionWithStatusMeta) -> bool {
-    let encoded_txn = rpc_txn.transaction.to_owned();
-    trace!("{:?}", encoded_txn);
+/// Whether a failed request may succeed when sent again.  Errors raised by
+/// the client itself, such as a request missing from a replay file, would
+/// only repeat.
+fn is_retryable(err: &ClientError) -> bool {
+    !matches!(err.kind(), ClientErrorKind::Custom(_))
+}
 
-    let json_txn = encoded_txn.transaction;
-    trace!("{:?}", json_txn);
+/// Doubles the delay with each attempt up to a ceiling, then randomizes the
+/// upper half of it so that parallel retries spread out instead of hitting
+/// the RPC node again in lockstep.
+fn retry_delay(attempt: u32) -> Duration {
+    let ceiling = BASE_RETRY_DELAY
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_RETRY_DELAY);
 
-    match json_txn {
-        EncodedTransaction::Json(json) => is_deployment_json(json),
-        _ => false,
-    }
+    ceiling / 2 + (ceiling / 2).mul_f64(rand::thread_rng().gen::<f64>())
 }
-#[instrument]
-fn is_deployment_json(json: UiTransaction) -> bool {
-    trace!("{:?}", json);
 
-    debug!(
-        "Checking if transaction {} is a deployment...",
-        json.signatures.first().unwrap()
-    );
+/// Warns about transactions missing from the search, since any of them
+/// could hide a deployment.  Offline, details missing from the cache were
+/// never requested, so they are reported as such rather than as failures.
+fn warn_on_gaps(
+    program_id: &Pubkey,
+    searched: usize,
+    unrecoverable: usize,
+    undecoded: usize,
+    offline: bool,
+) {
+    if unrecoverable > 0 && offline {
+        warn!(
+            "The details of {unrecoverable} of {searched} transactions for program_id: \
+             {program_id} are not in the cache, and offline mode does not request them.  Their \
+             signatures are listed with the result, which may be missing a deployment."
+        );
+    } else if unrecoverable > 0 {
+        warn!(
+            "The details of {unrecoverable} of {searched} transactions for program_id: \
+             {program_id} could not be retrieved after {MAX_FETCH_ATTEMPTS} attempts each.  Their \
+             signatures are listed with the result, which may be missing a deployment."
+        );
+    }
 
-    match json.message {
-        UiMessage::Parsed(message) => is_deployment_message(message),
-        _ => false,
+    if undecoded > 0 {
+        warn!(
+            "{undecoded} of {searched} transactions for program_id: {program_id} could not be \
+             decoded, so any deployment they carry is missing from the results.  Their signatures \
+             are listed with the result."
+        );
     }
 }
 
-#[instrument]
-fn is_deployment_message(message: UiParsedMessage) -> bool {
-    trace!("{:?}", message);
+/// Distinguishes a program with no deployment in its history from a history
+/// that could not be fully retrieved or decoded, since either could hide the
+/// deployment being searched for.
+fn missing_deployment_error(
+    program_id: &Pubkey,
+    unrecoverable: usize,
+    undecoded: usize,
+    offline: bool,
+) -> SolceptionError {
+    if unrecoverable > 0 && offline {
+        SolceptionError::NotCached {
+            what: format!("details of {unrecoverable} transactions for program_id: {program_id}"),
+        }
+    } else if unrecoverable > 0 {
+        SolceptionError::HistoryTruncated {
+            program_id: program_id.to_string(),
+            reason: format!("{unrecoverable} transaction details could not be retrieved"),
+        }
+    } else if undecoded > 0 {
+        SolceptionError::DecodeFailure {
+            what: format!("{undecoded} transactions for program_id: {program_id}"),
+            reason: "the RPC node returned an encoding this client cannot read".to_string(),
+        }
+    } else {
+        SolceptionError::NoDeploymentsFound {
+            program_id: program_id.to_string(),
+        }
+    }
+}
 
-    let bpf_loader_upgradeable = Pubkey::from_str("BPFLoaderUpgradeab1e11111111111111111111111")
-        .expect(
-            "This Solana system program ID failed to parse.  This should only occur if the Solana \
-             Labs crates have changed the system program ID.  Check the Git blame for the Solana \
-             Rust SDK to see if related changes were made to the Pubkey object's parser or the \
-             system IDs.",
+/// The ProgramData account is created by the initial deploy.  When a single
+/// page of its history reaches all the way back and holds no successful
+/// transaction before the last deployment slot, that deployment was also the
+/// first one.  A full page means the answer is unknown without a crawl, so
+/// the deployment is conservatively not flagged.
+#[instrument(skip(rpc_client))]
+fn is_first_deployment(
+    rpc_client: &RpcClient,
+    programdata_address: &Pubkey,
+    last_deployed_slot: u64,
+) -> Result<bool> {
+    let page = rpc_client
+        .get_signatures_for_address_with_config(
+            programdata_address,
+            GetConfirmedSignaturesForAddress2Config {
+                before: None,
+                until: None,
+                limit: None,
+                commitment: Some(rpc_client.commitment()),
+            },
+        )
+        .map_err(rpc_error)?;
+
+    if page.len() >= DEFAULT_SERVER_SIDE_BATCH_LIMIT {
+        debug!(
+            "ProgramData history for {programdata_address} spans more than one page, so the last \
+             deployment is not flagged as the inception."
         );
+        return Ok(false);
+    }
 
-    let acct_keys = message
-        .account_keys
-        .iter()
-        .map(|parsed_acct| parsed_acct.pubkey.to_string())
-        .collect::<Vec<_>>();
-    acct_keys
+    Ok(!page
         .iter()
-        .any(|acct_key| acct_key == &bpf_loader_upgradeable.to_string())
+        .any(|txn| txn.err.is_none() && txn.slot < last_deployed_slot))
+}
+
+/// Every deploy, upgrade, authority change, extension, and close of an
+/// upgradeable program writes to its ProgramData account, while user
+/// invocations only touch the program account.  Crawling the ProgramData
+/// account therefore yields the full deployment history in a handful of
+/// transactions instead of the program's entire invocation history.
+///
+/// The preflight reads which loader owns the program, and fails fast when
+/// the account is not a program at all.  Programs owned by any loader other
+/// than `BPFLoaderUpgradeab1e` have no ProgramData account, so their own
+/// history is crawled instead.  That includes programs migrated to
+/// `LoaderV4`, whose deploys before the migration also name the program
+/// account.  When no account exists at the program ID, as for some closed
+/// programs, the ProgramData address is derived from it.
+#[instrument(skip(rpc_client))]
+fn resolve_programdata_address(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<(Option<ProgramLoader>, Pubkey)> {
+    match preflight(rpc_client, program_id)? {
+        Some(program) => Ok((Some(program.loader), program.history_address)),
+        None => {
+            let derived_address = get_program_data_address(program_id);
+            debug!(
+                "No account exists for program {program_id}, so falling back to the derived \
+                 ProgramData address {derived_address}"
+            );
+            Ok((Some(ProgramLoader::BpfLoaderUpgradeable), derived_address))
+        }
+    }
+}
+
+/// `LoaderV4` deploys a program and redeploys it after a retract with the
+/// same instruction, so every successful deploy after the first is an
+/// upgrade.
+fn relabel_redeploys(deployments: &mut [Deployment]) {
+    let mut deployed = false;
+    for deployment in deployments
+        .iter_mut()
+        .filter(|deployment| deployment.kind == DeploymentKind::InitialDeploy)
+    {
+        if deployed {
+            deployment.kind = DeploymentKind::Upgrade;
+        }
+        deployed = true;
+    }
 }
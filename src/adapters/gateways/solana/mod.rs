@@ -1,3 +1,11 @@
+mod checkpoint;
+mod classification;
 pub mod interface;
+mod pipeline;
+mod preflight;
+mod replay;
+mod throttled_sender;
 
 pub(crate) use interface::SolanaRpc;
+pub(crate) use replay::{RecordingSender, ReplaySender};
+pub(crate) use throttled_sender::ThrottledHttpSender;
@@ -0,0 +1,494 @@
+use std::{
+    iter,
+    ops::ControlFlow,
+    sync::mpsc::sync_channel,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use tracing::{debug, instrument, trace, warn};
+
+use super::interface::{parse_signature, rpc_error};
+use crate::{
+    adapters::gateways::signals::InterruptFlag,
+    entities::{Deployment, DeploymentKind, SolceptionError},
+};
+
+pub(super) const DEFAULT_SERVER_SIDE_BATCH_LIMIT: usize = 1000;
+
+/// Pages of transaction summaries held between the crawl and fetch stages.
+const PAGE_BUFFER: usize = 2;
+
+/// Windows of transaction details held between the fetch and classify stages.
+const WINDOW_BUFFER: usize = 2;
+
+/// How often the scan state is handed to the checkpoint callback.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+pub(super) type SignaturePage = Vec<RpcConfirmedTransactionStatusWithSignature>;
+
+/// Folds classified transactions into a result, in the order the pipeline
+/// delivers them.
+pub(super) trait HistoryReducer {
+    /// Returns `ControlFlow::Break` once no later transaction can change the
+    /// result, which stops the pipeline early.
+    fn reduce(&mut self, deployments: Vec<Deployment>) -> ControlFlow<()>;
+}
+
+/// What the pipeline could not account for while scanning a history, and
+/// how far it got.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct HistoryScan {
+    pub searched: usize,
+    pub unrecoverable_signatures: Vec<String>,
+    pub undecoded_signatures: Vec<String>,
+
+    /// The first transaction classified, in the order of the scan.  For a
+    /// newest-first scan, this was the newest transaction in the history
+    /// when the scan started.
+    pub first_signature: Option<String>,
+
+    /// The last transaction classified, in the order of the scan, from
+    /// which an interrupted scan resumes.
+    pub last_signature: Option<String>,
+
+    #[serde(skip)]
+    pub interrupted: bool,
+}
+
+/// Streams a history through three stages connected by bounded channels:
+/// crawling pages of summaries, fetching transaction details in parallel
+/// windows, and classifying each transaction into the reducer on the calling
+/// thread.  Only a couple of pages and windows are ever held at once, so
+/// memory stays flat however long the history is.  When the reducer breaks,
+/// the channels close and the upstream stages stop at their next send.
+///
+/// The scan continues from the given state, which is handed to the
+/// checkpoint callback every few seconds, and again when the scan fails or
+/// is interrupted.  Both only happen between windows, so the state always
+/// covers whole windows.
+#[instrument(skip_all)]
+pub(super) fn run_pipeline<P, F, C, R, K>(
+    pages: P,
+    window_size: usize,
+    fetch_window: F,
+    classify: C,
+    reducer: &mut R,
+    mut scan: HistoryScan,
+    control: ScanControl<'_, K>,
+) -> Result<HistoryScan>
+where
+    P: Iterator<Item = Result<SignaturePage>> + Send,
+    F: Fn(&[Signature]) -> Vec<Result<EncodedConfirmedTransactionWithStatusMeta, SolceptionError>>
+        + Sync,
+    C: Fn(&EncodedConfirmedTransactionWithStatusMeta) -> Option<Vec<Deployment>>,
+    R: HistoryReducer,
+    K: FnMut(&HistoryScan, &R),
+{
+    let ScanControl {
+        interrupt,
+        mut checkpoint,
+    } = control;
+
+    thread::scope(|scope| {
+        let (page_sender, page_receiver) = sync_channel::<Result<SignaturePage>>(PAGE_BUFFER);
+        let (window_sender, window_receiver) = sync_channel(WINDOW_BUFFER);
+
+        scope.spawn(move || {
+            for page in pages {
+                if page_sender.send(page).is_err() {
+                    trace!("Crawl stage stopping, as the pipeline has closed.");
+                    return;
+                }
+            }
+        });
+
+        let fetch_window = &fetch_window;
+        scope.spawn(move || {
+            for page in page_receiver {
+                let signatures = page.and_then(|page| {
+                    page.iter()
+                        .map(|txn| parse_signature(&txn.signature).map_err(Into::into))
+                        .collect::<Result<Vec<_>>>()
+                });
+                let signatures = match signatures {
+                    Ok(signatures) => signatures,
+                    Err(e) => {
+                        let _ = window_sender.send(Err(e));
+                        return;
+                    }
+                };
+
+                for window in signatures.chunks(window_size) {
+                    let fetched = window.iter().copied().zip(fetch_window(window)).collect();
+                    if window_sender
+                        .send(Ok::<Vec<_>, anyhow::Error>(fetched))
+                        .is_err()
+                    {
+                        trace!("Fetch stage stopping, as the pipeline has closed.");
+                        return;
+                    }
+                }
+            }
+        });
+
+        let mut checkpointed_at = Instant::now();
+        for window in window_receiver {
+            let window = match window {
+                Ok(window) => window,
+                Err(e) => {
+                    checkpoint(&scan, reducer);
+                    return Err(e);
+                }
+            };
+            let first_signature = window.first().map(|(signature, _)| signature.to_string());
+            let last_signature = window.last().map(|(signature, _)| signature.to_string());
+
+            for (signature, result) in window {
+                scan.searched += 1;
+
+                let txn = match result {
+                    Ok(txn) => txn,
+                    Err(e) => {
+                        debug!("Giving up on transaction {signature}: {e}");
+                        scan.unrecoverable_signatures.push(signature.to_string());
+                        continue;
+                    }
+                };
+
+                let Some(deployments) = classify(&txn) else {
+                    scan.undecoded_signatures.push(signature.to_string());
+                    continue;
+                };
+
+                if reducer.reduce(deployments).is_break() {
+                    debug!(
+                        "Stopping the history scan after {} transactions.",
+                        scan.searched
+                    );
+                    return Ok(scan);
+                }
+            }
+            if scan.first_signature.is_none() {
+                scan.first_signature = first_signature;
+            }
+            scan.last_signature = last_signature;
+
+            if interrupt.is_requested() {
+                debug!(
+                    "Stopping the history scan after {} transactions, as it was interrupted.",
+                    scan.searched
+                );
+                scan.interrupted = true;
+                checkpoint(&scan, reducer);
+                return Ok(scan);
+            }
+            if checkpointed_at.elapsed() >= CHECKPOINT_INTERVAL {
+                checkpoint(&scan, reducer);
+                checkpointed_at = Instant::now();
+            }
+        }
+
+        Ok(scan)
+    })
+}
+
+/// How a running scan is stopped early and its progress saved.
+pub(super) struct ScanControl<'a, K> {
+    /// Checked after every window.
+    pub interrupt: &'a InterruptFlag,
+    pub checkpoint: K,
+}
+
+/// Skips the pages up to and including the given signature, for resuming a
+/// scan after the last transaction it classified.  A signature missing from
+/// every page means the history is not the one the checkpoint was saved
+/// against, so rather than skipping the whole history, the pages end with an
+/// error.
+pub(super) fn resume_after<P>(
+    pages: P,
+    last_signature: Option<String>,
+) -> impl Iterator<Item = Result<SignaturePage>> + Send
+where
+    P: Iterator<Item = Result<SignaturePage>> + Send,
+{
+    let mut skipping = last_signature;
+    let mut pages = pages.fuse();
+
+    iter::from_fn(move || loop {
+        let Some(page) = pages.next() else {
+            let last_signature = skipping.take()?;
+            return Some(Err(anyhow!(
+                "The checkpoint's last transaction {last_signature} is no longer in the history, \
+                 which may have changed or come from a different RPC node, so the lookup cannot \
+                 be resumed.  Run again without `--resume` to start over."
+            )));
+        };
+        let Some(last_signature) = &skipping else {
+            return Some(page);
+        };
+
+        match page {
+            Ok(page) => {
+                if let Some(position) = page.iter().position(|txn| txn.signature == *last_signature)
+                {
+                    trace!("Resuming the history scan after {last_signature}");
+                    skipping = None;
+                    return Some(Ok(page[position + 1..].to_vec()));
+                }
+            }
+            Err(e) => return Some(Err(e)),
+        }
+    })
+}
+
+/// Refuses to resume a newest-first scan when the history has gained
+/// transactions since the scan started, as continuing after its last
+/// signature would leave them out of the result.
+pub(super) fn ensure_not_stale(scan: &HistoryScan, newest_signature: Option<&str>) -> Result<()> {
+    let (Some(first_signature), Some(_)) = (&scan.first_signature, &scan.last_signature) else {
+        return Ok(());
+    };
+
+    if newest_signature != Some(first_signature.as_str()) {
+        return Err(anyhow!(
+            "The history has transactions newer than {first_signature}, the newest when the \
+             checkpoint was saved, which resuming would leave out.  Run again without `--resume` \
+             to start over."
+        ));
+    }
+
+    Ok(())
+}
+
+/// Finds the successful initial deploy, keeping the failed attempts that
+/// preceded it.  Fed oldest-first, the first one found is the inception, so
+/// the scan stops there.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct InceptionSearch {
+    pub inception: Option<Deployment>,
+    pub failed_attempts: Vec<Deployment>,
+}
+
+impl HistoryReducer for InceptionSearch {
+    fn reduce(&mut self, deployments: Vec<Deployment>) -> ControlFlow<()> {
+        for deployment in deployments {
+            if !deployment.succeeded() {
+                self.failed_attempts.push(deployment);
+            } else if deployment.kind == DeploymentKind::InitialDeploy {
+                self.inception = Some(deployment);
+                break;
+            }
+        }
+
+        match self.inception {
+            Some(_) => ControlFlow::Break(()),
+            None => ControlFlow::Continue(()),
+        }
+    }
+}
+
+/// Gathers every deployment, keeping failed attempts apart.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct TimelineBuilder {
+    pub deployments: Vec<Deployment>,
+    pub failed_attempts: Vec<Deployment>,
+}
+
+impl HistoryReducer for TimelineBuilder {
+    fn reduce(&mut self, deployments: Vec<Deployment>) -> ControlFlow<()> {
+        for deployment in deployments {
+            if deployment.succeeded() {
+                self.deployments.push(deployment);
+            } else {
+                self.failed_attempts.push(deployment);
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Pages of an address's transaction summaries, newest first, requested one
+/// at a time with the `before` cursor.  With an `until` signature, the crawl
+/// stops short of it, so only transactions newer than it are returned.
+pub(super) struct HistoryPages<'a> {
+    rpc_client: &'a RpcClient,
+    address: Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    crawled: usize,
+    done: bool,
+}
+
+impl<'a> HistoryPages<'a> {
+    pub fn new(rpc_client: &'a RpcClient, address: Pubkey, until: Option<Signature>) -> Self {
+        debug!("Retrieving transaction summaries for {address} until {until:?}");
+
+        Self {
+            rpc_client,
+            address,
+            before: None,
+            until,
+            crawled: 0,
+            done: false,
+        }
+    }
+
+    /// Starts the crawl just past the given signature instead of at the
+    /// newest transaction.
+    pub fn starting_before(mut self, before: Option<Signature>) -> Self {
+        self.before = before;
+        self
+    }
+
+    fn next_page(&mut self) -> Result<SignaturePage> {
+        let page = fetch_page(self.rpc_client, &self.address, self.before, self.until)?;
+
+        if page.len() < DEFAULT_SERVER_SIDE_BATCH_LIMIT {
+            trace!("Exiting history crawl loop.");
+            self.done = true;
+        } else {
+            trace!("Continuing history crawl loop...");
+        }
+
+        self.before = page
+            .last()
+            .map(|txn| parse_signature(&txn.signature))
+            .transpose()?;
+
+        let previously_crawled = self.crawled;
+        self.crawled += page.len();
+        if previously_crawled <= DEFAULT_SERVER_SIDE_BATCH_LIMIT
+            && self.crawled > DEFAULT_SERVER_SIDE_BATCH_LIMIT
+        {
+            warn!(
+                "The number of transactions for {} exceeds 1000.  This may take a long time to \
+                 retrieve all transaction details, depending on the chosen cluster RPC node's \
+                 rate limits!",
+                self.address
+            );
+        }
+
+        Ok(page)
+    }
+}
+
+impl Iterator for HistoryPages<'_> {
+    type Item = Result<SignaturePage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let page = self.next_page();
+        if page.is_err() {
+            self.done = true;
+        }
+
+        Some(page)
+    }
+}
+
+/// Pages of an address's transaction summaries, oldest first, with each page
+/// in slot order.  The newest-first crawl keeps only the `before` cursor of
+/// each page rather than its summaries, and the pages are requested again in
+/// reverse as the scan reaches them.  The oldest page is kept from the crawl,
+/// so a history that fits in a single page is only requested once.
+pub(super) struct OldestFirstPages<'a> {
+    rpc_client: &'a RpcClient,
+    address: Pubkey,
+    cursors: Vec<Option<Signature>>,
+    oldest_page: Option<SignaturePage>,
+}
+
+impl<'a> OldestFirstPages<'a> {
+    #[instrument(skip(rpc_client))]
+    pub fn crawl(rpc_client: &'a RpcClient, address: Pubkey) -> Result<Self> {
+        let mut pages = HistoryPages::new(rpc_client, address, None);
+        let mut cursors = Vec::new();
+        let mut oldest_page = None;
+
+        loop {
+            let cursor = pages.before;
+            let Some(page) = pages.next() else {
+                break;
+            };
+
+            // Each page replaces the newer one before it, so only the oldest
+            // page is held once the crawl completes.
+            oldest_page = Some(page?);
+            cursors.push(cursor);
+        }
+
+        // The oldest page is already held, so its own cursor is not needed.
+        cursors.pop();
+        debug!(
+            "Crawled {} transaction summaries for {address} across {} pages.",
+            pages.crawled,
+            cursors.len() + 1
+        );
+
+        Ok(Self {
+            rpc_client,
+            address,
+            cursors,
+            oldest_page: oldest_page.map(|mut page| {
+                page.reverse();
+                page
+            }),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursors.is_empty() && self.oldest_page.as_deref().unwrap_or_default().is_empty()
+    }
+}
+
+impl Iterator for OldestFirstPages<'_> {
+    type Item = Result<SignaturePage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(page) = self.oldest_page.take() {
+            return Some(Ok(page));
+        }
+
+        let cursor = self.cursors.pop()?;
+        let page = fetch_page(self.rpc_client, &self.address, cursor, None).map(|mut page| {
+            page.reverse();
+            page
+        });
+
+        Some(page)
+    }
+}
+
+fn fetch_page(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+) -> Result<SignaturePage> {
+    let page = rpc_client
+        .get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: None,
+                commitment: Some(rpc_client.commitment()),
+            },
+        )
+        .map_err(rpc_error)?;
+
+    Ok(page)
+}
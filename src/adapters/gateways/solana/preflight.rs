@@ -0,0 +1,195 @@
+use anyhow::Result;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::{account::Account, account_utils::StateMut, pubkey, pubkey::Pubkey};
+use solana_sdk_ids::{bpf_loader_upgradeable, native_loader, stake, system_program, sysvar, vote};
+use tracing::{debug, instrument};
+
+use super::{classification::ProgramLoader, interface::rpc_error};
+use crate::entities::SolceptionError;
+
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The sizes of the accounts whose kind is told apart by their size alone.
+const NONCE_ACCOUNT_LEN: usize = 80;
+const TOKEN_MINT_LEN: usize = 82;
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_MULTISIG_LEN: usize = 355;
+
+/// The size of an upgradeable program account: the `Program` variant tag
+/// followed by its ProgramData address.
+const UPGRADEABLE_PROGRAM_LEN: u64 = 36;
+
+/// A program account that passed the preflight.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ProgramAccount {
+    pub loader: ProgramLoader,
+
+    /// The ProgramData account of an upgradeable program, or the program
+    /// account itself for every other loader.
+    pub history_address: Pubkey,
+}
+
+/// Reads the target account before any history is crawled, and fails fast
+/// with what the account actually is when it is not a deployed program.
+/// Returns `None` when no account exists at the address, which is also the
+/// case for some closed programs, so the caller decides whether that is an
+/// error.
+#[instrument(skip(rpc_client))]
+pub(super) fn preflight(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+) -> Result<Option<ProgramAccount>> {
+    let Some(account) = rpc_client
+        .get_account_with_commitment(address, rpc_client.commitment())
+        .map_err(rpc_error)?
+        .value
+    else {
+        debug!("No account exists at {address}");
+        return Ok(None);
+    };
+
+    let not_a_program = |reason: String| SolceptionError::NotAProgram {
+        address: address.to_string(),
+        reason,
+    };
+
+    let Some(loader) = ProgramLoader::from_id(&account.owner) else {
+        return Err(not_a_program(describe_account(&account)).into());
+    };
+
+    match loader {
+        ProgramLoader::BpfLoaderUpgradeable => match account.state() {
+            Ok(UpgradeableLoaderState::Program {
+                programdata_address,
+            }) => {
+                debug!("Program {address} stores its ProgramData at {programdata_address}");
+                Ok(Some(ProgramAccount {
+                    loader,
+                    history_address: programdata_address,
+                }))
+            }
+            Ok(UpgradeableLoaderState::ProgramData { .. }) => {
+                let reason = match find_program_of(rpc_client, address) {
+                    Ok(Some(program_id)) => format!(
+                        "it is the ProgramData account of program {program_id}, which holds the \
+                         program's code.  Look up {program_id} instead"
+                    ),
+                    Ok(None) => "it is a ProgramData account, which holds a program's code, but \
+                                 no program refers to it.  Look up the program ID instead"
+                        .to_string(),
+                    Err(e) => format!(
+                        "it is a ProgramData account, which holds a program's code, and the RPC \
+                         node could not find the program it belongs to: {e}.  Look up the program \
+                         ID instead"
+                    ),
+                };
+
+                Err(not_a_program(reason).into())
+            }
+            Ok(UpgradeableLoaderState::Buffer { .. }) => Err(not_a_program(
+                "it is a buffer account holding program code written for a deploy or upgrade, not \
+                 a program.  Look up the program ID instead"
+                    .to_string(),
+            )
+            .into()),
+            _ => Err(not_a_program(
+                "it is owned by BPFLoaderUpgradeab1e but holds no initialized program".to_string(),
+            )
+            .into()),
+        },
+        ProgramLoader::BpfLoaderDeprecated | ProgramLoader::BpfLoader if !account.executable => {
+            Err(not_a_program(format!(
+                "it is owned by {loader} but was never finalized, so it was never deployed"
+            ))
+            .into())
+        }
+        // A LoaderV4 program is searched whatever its status, since one
+        // that was retracted still has a deployment history.
+        ProgramLoader::BpfLoaderDeprecated | ProgramLoader::BpfLoader | ProgramLoader::LoaderV4 => {
+            debug!(
+                "Program {address} is owned by {loader}, so crawling the program account itself."
+            );
+            Ok(Some(ProgramAccount {
+                loader,
+                history_address: *address,
+            }))
+        }
+    }
+}
+
+/// An upgradeable program account holds nothing but the address of its
+/// ProgramData account, so the program is the one loader account of that
+/// size holding the address.
+#[instrument(skip(rpc_client))]
+fn find_program_of(rpc_client: &RpcClient, programdata_address: &Pubkey) -> Result<Option<Pubkey>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(UPGRADEABLE_PROGRAM_LEN),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(4, programdata_address.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(rpc_client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let programs = rpc_client
+        .get_program_accounts_with_config(&bpf_loader_upgradeable::id(), config)
+        .map_err(rpc_error)?;
+
+    Ok(programs.first().map(|(program_id, _)| *program_id))
+}
+
+/// Names the kind of a non-program account from its owner and, where the
+/// owner keeps several kinds, its size.
+fn describe_account(account: &Account) -> String {
+    let owner = account.owner;
+    let len = account.data.len();
+
+    if owner == system_program::id() {
+        return match len {
+            0 => "it is a wallet, an account holding only SOL".to_string(),
+            NONCE_ACCOUNT_LEN => "it is a durable nonce account".to_string(),
+            _ => format!("it is an account of {len} bytes owned by the System Program"),
+        };
+    }
+
+    if owner == TOKEN_PROGRAM_ID || owner == TOKEN_2022_PROGRAM_ID {
+        // Token-2022 pads accounts with extensions past the size of a token
+        // account and marks their kind in the byte that follows.
+        let kind = match len {
+            TOKEN_MINT_LEN => "token mint",
+            TOKEN_ACCOUNT_LEN => "token account",
+            TOKEN_MULTISIG_LEN => "token multisig",
+            _ => match account.data.get(TOKEN_ACCOUNT_LEN) {
+                Some(1) => "token mint",
+                Some(2) => "token account",
+                _ => "token program account",
+            },
+        };
+        return format!("it is a {kind} owned by {owner}");
+    }
+
+    if owner == stake::id() {
+        return "it is a stake account".to_string();
+    }
+    if owner == vote::id() {
+        return "it is a vote account".to_string();
+    }
+    if owner == sysvar::id() {
+        return "it is a sysvar, which the runtime maintains".to_string();
+    }
+    if owner == native_loader::id() {
+        return "it is a builtin program, which is part of the validator rather than deployed to \
+                the chain"
+            .to_string();
+    }
+
+    format!("it is a data account of {len} bytes owned by program {owner}")
+}
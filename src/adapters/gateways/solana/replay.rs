@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use tracing::{debug, instrument, trace};
+
+/// One JSON-RPC request and the node's answer to it, stored one per line in
+/// a fixture file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    params: Value,
+
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Result(Value),
+    Error { code: i64, message: String },
+}
+
+/// Requests are matched on their method and parameters alone, since the
+/// parallel fetches make their order nondeterministic.
+fn exchange_key(method: &str, params: &Value) -> String {
+    format!("{method} {params}")
+}
+
+/// Wraps another transport and appends every exchange with the RPC node to a
+/// fixture file, for [`ReplaySender`] to serve later.  Responses and RPC
+/// errors are recorded; transport failures are not, since they say nothing
+/// about the ledger.
+pub(crate) struct RecordingSender<T> {
+    inner: T,
+    path: PathBuf,
+    fixture: Mutex<BufWriter<File>>,
+}
+
+impl<T: RpcSender> RecordingSender<T> {
+    #[instrument(skip(inner))]
+    pub fn create(inner: T, path: &Path) -> Result<Self> {
+        let file = File::create(path).map_err(|e| {
+            anyhow!(
+                "Failed to create the recording file at {} .  Check that its directory exists and \
+                 is writable.  {e}",
+                path.display()
+            )
+        })?;
+
+        Ok(Self {
+            inner,
+            path: path.to_path_buf(),
+            fixture: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn record(&self, exchange: &Exchange) -> io::Result<()> {
+        let mut fixture = self.fixture.lock().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_writer(&mut *fixture, exchange)?;
+        fixture.write_all(b"\n")?;
+        fixture.flush()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: RpcSender + Send + Sync> RpcSender for RecordingSender<T> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let result = self.inner.send(request, params.clone()).await;
+
+        let outcome = match &result {
+            Ok(value) => Outcome::Result(value.clone()),
+            Err(e) => match e.kind() {
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+                    Outcome::Error {
+                        code: *code,
+                        message: message.clone(),
+                    }
+                }
+                _ => {
+                    debug!("Not recording the transport failure of {request}: {e}");
+                    return result;
+                }
+            },
+        };
+
+        trace!("Recording {request} to {}", self.path.display());
+        self.record(&Exchange {
+            method: request.to_string(),
+            params,
+            outcome,
+        })?;
+
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Serves the exchanges of a fixture file recorded by [`RecordingSender`]
+/// without any network access.  Repeated requests are answered in the order
+/// they were recorded, with the last answer repeated once they run out, and
+/// a request that was never recorded fails as a client error, which is never
+/// retried since it would only fail again.
+pub(crate) struct ReplaySender {
+    path: PathBuf,
+    outcomes: HashMap<String, Vec<Outcome>>,
+    served: Mutex<HashMap<String, usize>>,
+    stats: Mutex<RpcTransportStats>,
+}
+
+impl ReplaySender {
+    #[instrument]
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|e| {
+            anyhow!(
+                "Failed to open the replay file at {} .  Check that it exists, or record one with \
+                 `--record`.  {e}",
+                path.display()
+            )
+        })?;
+
+        let mut outcomes: HashMap<String, Vec<Outcome>> = HashMap::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let exchange: Exchange = serde_json::from_str(&line).map_err(|e| {
+                anyhow!(
+                    "Failed to parse line {} of the replay file at {} .  Check that it was \
+                     written by `--record`.  {e}",
+                    index + 1,
+                    path.display()
+                )
+            })?;
+            outcomes
+                .entry(exchange_key(&exchange.method, &exchange.params))
+                .or_default()
+                .push(exchange.outcome);
+        }
+        debug!(
+            "Loaded {} distinct requests from {}",
+            outcomes.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            outcomes,
+            served: Mutex::new(HashMap::new()),
+            stats: Mutex::new(RpcTransportStats::default()),
+        })
+    }
+}
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .request_count += 1;
+
+        let key = exchange_key(&request.to_string(), &params);
+        let Some(outcomes) = self.outcomes.get(&key) else {
+            return Err(ClientErrorKind::Custom(format!(
+                "the replay file {} has no recorded response for {key}",
+                self.path.display()
+            ))
+            .into());
+        };
+
+        let index = {
+            let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+            let count = served.entry(key).or_default();
+            *count += 1;
+            (*count - 1).min(outcomes.len() - 1)
+        };
+        trace!("Replaying response {index} for {request}");
+
+        match outcomes[index].clone() {
+            Outcome::Result(value) => Ok(value),
+            Outcome::Error { code, message } => Err(RpcError::RpcResponseError {
+                code,
+                message,
+                data: RpcResponseErrorData::Empty,
+            }
+            .into()),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn url(&self) -> String {
+        format!("replay:{}", self.path.display())
+    }
+}
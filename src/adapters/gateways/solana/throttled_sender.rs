@@ -0,0 +1,398 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::prelude::*;
+use serde_json::Value;
+use solana_client::{
+    client_error::{
+        reqwest::{
+            self,
+            header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER},
+            StatusCode,
+        },
+        Result as ClientResult,
+    },
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use tokio::{sync::Semaphore, time::sleep};
+use tracing::{debug, instrument, trace, warn};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_THROTTLED_RETRIES: u32 = 5;
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// The share of the configured rate regained after each successful request.
+const ADDITIVE_INCREASE_FRACTION: f64 = 0.02;
+const MULTIPLICATIVE_DECREASE: f64 = 0.5;
+const MIN_REQUESTS_PER_SECOND: f64 = 0.5;
+
+/// An HTTP transport for the RPC client that paces every request through a
+/// shared token bucket and caps the number of requests in flight, so the
+/// parallel detail fetches stay within the limits of the RPC node instead of
+/// tripping its throttling.  Throttled responses halve the request rate and
+/// pause the whole pool for the node's `Retry-After`, while each success wins
+/// back a little of the configured rate.
+pub(crate) struct ThrottledHttpSender {
+    client: reqwest::Client,
+    url: String,
+    request_id: AtomicU64,
+    stats: RwLock<RpcTransportStats>,
+    bucket: TokenBucket,
+    in_flight: Semaphore,
+}
+
+impl ThrottledHttpSender {
+    #[instrument]
+    pub fn new(
+        url: &str,
+        timeout: Option<Duration>,
+        requests_per_second: u32,
+        max_in_flight: usize,
+    ) -> Self {
+        let timeout = timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .pool_idle_timeout(timeout)
+            .build()
+            .expect(
+                "Failed to build the HTTP client for the RPC node.  This should only occur if the \
+                 TLS backend could not be initialized on this system.",
+            );
+
+        Self {
+            client,
+            url: url.to_string(),
+            request_id: AtomicU64::new(0),
+            stats: RwLock::new(RpcTransportStats::default()),
+            bucket: TokenBucket::new(f64::from(requests_per_second)),
+            in_flight: Semaphore::new(max_in_flight),
+        }
+    }
+
+    fn record_request(&self, started_at: Instant, rate_limited_time: Duration) {
+        let mut stats = self.stats.write().unwrap_or_else(|e| e.into_inner());
+        stats.request_count += 1;
+        stats.elapsed_time += started_at.elapsed();
+        stats.rate_limited_time += rate_limited_time;
+    }
+}
+
+#[async_trait]
+impl RpcSender for ThrottledHttpSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let started_at = Instant::now();
+        let mut rate_limited_time = Duration::ZERO;
+
+        let _permit = self.in_flight.acquire().await.expect(
+            "The in-flight request semaphore was closed.  It is owned by the sender and never \
+             closed, so check the Git blame for this module to see if that has changed.",
+        );
+
+        let request_id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let request_json = request.build_request_json(request_id, params).to_string();
+
+        let mut throttled_retries = 0;
+        let response = loop {
+            rate_limited_time += self.bucket.acquire().await;
+
+            let response = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(request_json.clone())
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                self.bucket.on_success();
+                break response;
+            }
+
+            let retry_after = parse_retry_after(response.headers());
+            self.bucket.on_throttled(retry_after);
+
+            if throttled_retries >= MAX_THROTTLED_RETRIES {
+                warn!(
+                    "The RPC node is still rate limiting {request} after {throttled_retries} \
+                     retries.  Lower `--rps` or `--concurrency` to match its limits."
+                );
+                self.record_request(started_at, rate_limited_time);
+                return Err(response.error_for_status().unwrap_err().into());
+            }
+            throttled_retries += 1;
+            debug!(
+                "The RPC node rate limited {request}, pausing all requests for {retry_after:?} \
+                 (retry {throttled_retries} of {MAX_THROTTLED_RETRIES})."
+            );
+        };
+
+        self.record_request(started_at, rate_limited_time);
+
+        let mut json = response.error_for_status()?.json::<Value>().await?;
+        trace!("{request} response: {json}");
+
+        if json["error"].is_object() {
+            let error = &json["error"];
+            return Err(RpcError::RpcResponseError {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+                data: RpcResponseErrorData::Empty,
+            }
+            .into());
+        }
+
+        Ok(json["result"].take())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// `Retry-After` holds either a number of seconds or an HTTP date.  Missing
+/// or unparsable values fall back to a short pause, and values beyond a
+/// couple of minutes are capped so a misbehaving node cannot stall the
+/// lookup indefinitely.
+fn parse_retry_after(headers: &HeaderMap) -> Duration {
+    let Some(value) = headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return DEFAULT_RETRY_AFTER;
+    };
+
+    let retry_after = if let Ok(seconds) = value.trim().parse::<u64>() {
+        Duration::from_secs(seconds)
+    } else if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+    } else {
+        debug!("Ignoring unparsable Retry-After header: {value}");
+        DEFAULT_RETRY_AFTER
+    };
+
+    retry_after.min(MAX_RETRY_AFTER)
+}
+
+/// A token bucket whose refill rate adapts to the RPC node: additive
+/// increase on success, multiplicative decrease on throttling.
+struct TokenBucket {
+    max_rate: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+    paused_until: Instant,
+}
+
+impl TokenBucket {
+    fn new(max_rate: f64) -> Self {
+        let now = Instant::now();
+
+        Self {
+            max_rate,
+            state: Mutex::new(BucketState {
+                rate: max_rate,
+                tokens: max_rate.max(1.0),
+                refilled_at: now,
+                paused_until: now,
+            }),
+        }
+    }
+
+    /// Waits until a token is available and takes it.  Returns the time spent
+    /// waiting.
+    async fn acquire(&self) -> Duration {
+        let mut waited = Duration::ZERO;
+        loop {
+            let wait = self.reserve();
+            if wait.is_zero() {
+                return waited;
+            }
+
+            sleep(wait).await;
+            waited += wait;
+        }
+    }
+
+    /// Takes a token when one is available, and otherwise returns how long
+    /// until the next one is.
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        if now < state.paused_until {
+            return state.paused_until - now;
+        }
+
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * state.rate).min(state.rate.max(1.0));
+        state.refilled_at = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - state.tokens) / state.rate)
+        }
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.rate = (state.rate + self.max_rate * ADDITIVE_INCREASE_FRACTION).min(self.max_rate);
+    }
+
+    fn on_throttled(&self, retry_after: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.rate = (state.rate * MULTIPLICATIVE_DECREASE).max(MIN_REQUESTS_PER_SECOND);
+        state.tokens = 0.0;
+        state.paused_until = state.paused_until.max(Instant::now() + retry_after);
+        debug!(
+            "Reduced the request rate to {:.2} requests per second.",
+            state.rate
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::client_error::reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn retry_after(value: HeaderValue) -> Duration {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, value);
+        parse_retry_after(&headers)
+    }
+
+    fn http_date(date: DateTime<Utc>) -> HeaderValue {
+        HeaderValue::from_str(&date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()).unwrap()
+    }
+
+    fn rate(bucket: &TokenBucket) -> f64 {
+        bucket.state.lock().unwrap().rate
+    }
+
+    #[test]
+    fn retry_after_in_seconds_is_honored_up_to_the_cap() {
+        assert_eq!(
+            retry_after(HeaderValue::from_static("7")),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            retry_after(HeaderValue::from_static(" 30 ")),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            retry_after(HeaderValue::from_static("3600")),
+            MAX_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn retry_after_as_an_http_date_waits_until_that_date() {
+        let wait = retry_after(http_date(Utc::now() + chrono::Duration::seconds(60)));
+        assert!(
+            wait > Duration::from_secs(55) && wait <= Duration::from_secs(60),
+            "waited {wait:?}"
+        );
+
+        let past = retry_after(http_date(Utc::now() - chrono::Duration::seconds(60)));
+        assert_eq!(past, Duration::ZERO);
+
+        let far = retry_after(http_date(Utc::now() + chrono::Duration::hours(1)));
+        assert_eq!(far, MAX_RETRY_AFTER);
+    }
+
+    #[test]
+    fn missing_or_unparsable_retry_after_falls_back_to_the_default() {
+        assert_eq!(parse_retry_after(&HeaderMap::new()), DEFAULT_RETRY_AFTER);
+        assert_eq!(
+            retry_after(HeaderValue::from_static("soon")),
+            DEFAULT_RETRY_AFTER
+        );
+        assert_eq!(
+            retry_after(HeaderValue::from_static("-5")),
+            DEFAULT_RETRY_AFTER
+        );
+        assert_eq!(
+            retry_after(HeaderValue::from_bytes(b"\xff\xfe").unwrap()),
+            DEFAULT_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn throttling_halves_the_rate_down_to_the_minimum() {
+        let bucket = TokenBucket::new(10.0);
+
+        bucket.on_throttled(Duration::ZERO);
+        assert_eq!(rate(&bucket), 5.0);
+        bucket.on_throttled(Duration::ZERO);
+        assert_eq!(rate(&bucket), 2.5);
+
+        for _ in 0..10 {
+            bucket.on_throttled(Duration::ZERO);
+        }
+        assert_eq!(rate(&bucket), MIN_REQUESTS_PER_SECOND);
+    }
+
+    #[test]
+    fn successes_recover_the_rate_additively_up_to_the_configured_rate() {
+        let bucket = TokenBucket::new(10.0);
+        bucket.on_throttled(Duration::ZERO);
+
+        bucket.on_success();
+        assert!((rate(&bucket) - 5.2).abs() < 1e-9, "rate {}", rate(&bucket));
+        bucket.on_success();
+        assert!((rate(&bucket) - 5.4).abs() < 1e-9, "rate {}", rate(&bucket));
+
+        for _ in 0..100 {
+            bucket.on_success();
+        }
+        assert_eq!(rate(&bucket), 10.0);
+    }
+
+    #[test]
+    fn reserve_takes_tokens_until_the_bucket_is_empty() {
+        let bucket = TokenBucket::new(2.0);
+
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+
+        let wait = bucket.reserve();
+        assert!(
+            wait > Duration::from_millis(400) && wait <= Duration::from_millis(500),
+            "waited {wait:?}"
+        );
+    }
+
+    #[test]
+    fn reserve_waits_out_the_pause_after_throttling() {
+        let bucket = TokenBucket::new(100.0);
+        bucket.on_throttled(Duration::from_secs(5));
+
+        let wait = bucket.reserve();
+        assert!(
+            wait > Duration::from_millis(4900) && wait <= Duration::from_secs(5),
+            "waited {wait:?}"
+        );
+    }
+}
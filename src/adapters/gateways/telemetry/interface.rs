@@ -1,28 +1,129 @@
-#![allow(unused_imports, unused_variables, dead_code)]
+use std::{fmt, fs::OpenOptions, path::PathBuf, str::FromStr, sync::Mutex};
 
 use anyhow::{anyhow, Result};
-use tracing::{instrument, trace, Level};
+use tracing::{instrument, trace};
 use tracing_subscriber::{
-    filter::{filter_fn, LevelFilter},
-    fmt::{format::FmtSpan, writer::MakeWriterExt},
+    filter::{EnvFilter, LevelFilter},
+    fmt::writer::BoxMakeWriter,
     prelude::*,
+    Layer,
+    Registry,
 };
 
+/// The layouts log events can be written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Multi-line events for reading in a terminal.
+    #[default]
+    Pretty,
+
+    /// One line per event.
+    Compact,
+
+    /// One JSON object per line, with the fields of the enclosing spans, for
+    /// shipping to a log pipeline.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "pretty" => Ok(LogFormat::Pretty),
+            "compact" => Ok(LogFormat::Compact),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!(
+                "Unknown log format: {format} .  Use one of pretty, compact, or json."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            LogFormat::Pretty => "pretty",
+            LogFormat::Compact => "compact",
+            LogFormat::Json => "json",
+        };
+
+        f.write_str(label)
+    }
+}
+
+/// Caller-supplied settings for the subscriber installed by [`init_tracing`].
+#[derive(Debug, Default, Clone)]
+pub struct TracingOptions {
+    /// Verbosity, as the number of times `-v` was given to `solc`.  Sets the
+    /// level of every event not matched by a more specific directive.
+    pub debug_level: u8,
+
+    /// `RUST_LOG`-style directives, e.g. `solception=debug,hyper=off`.
+    /// Defaults to the `RUST_LOG` environment variable.
+    pub filter: Option<String>,
+
+    pub format: LogFormat,
+
+    /// File to append log events to instead of stderr.
+    pub log_file: Option<PathBuf>,
+}
+
+/// Installs a global subscriber for the spans and events the lookups emit.
+/// Fails rather than replacing a subscriber that is already installed, so
+/// host programs can call it unconditionally and keep their own.
+///
+/// Every event is written to stderr, or appended to the log file when one is
+/// given, so stdout carries nothing but results.
 #[instrument]
-pub(crate) fn init_tracing(debug_level: u8) -> Result<()> {
-    let writer = std::io::stderr
-        .with_max_level(Level::WARN)
-        .or_else(std::io::stdout);
+pub fn init_tracing(options: &TracingOptions) -> Result<()> {
+    let level = as_level_filter(options.debug_level);
+    let filter_builder = EnvFilter::builder().with_default_directive(level.into());
+    let filter = match &options.filter {
+        Some(directives) => filter_builder.parse(directives).map_err(|e| {
+            anyhow!(
+                "Failed to parse the log filter: {directives} .  Use `RUST_LOG`-style directives, \
+                 e.g. `solception=debug,hyper=off`.  {e}"
+            )
+        })?,
+        None => filter_builder.from_env_lossy(),
+    };
 
-    tracing_subscriber::fmt()
+    let writer = match &options.log_file {
+        Some(log_file) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to open the log file at {} .  Check that its directory exists and \
+                         is writable.  {e}",
+                        log_file.display()
+                    )
+                })?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
+
+    let layer = tracing_subscriber::fmt::layer()
         .with_writer(writer)
-        .pretty()
-        .with_max_level(as_level_filter(debug_level))
-        .init();
+        .with_ansi(options.log_file.is_none());
+    let layer: Box<dyn Layer<Registry> + Send + Sync> = match options.format {
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(layer.with_filter(filter))
+        .try_init()
+        .map_err(|e| anyhow!("Failed to install the tracing subscriber.  {e}"))?;
 
     trace!(
         "Initializing tracing with debug level: {}",
-        as_level_filter(debug_level).to_string().to_uppercase()
+        level.to_string().to_uppercase()
     );
 
     Ok(())
@@ -1,3 +1,3 @@
 pub mod interface;
 
-pub(crate) use interface::init_tracing;
+pub use interface::{init_tracing, LogFormat, TracingOptions};
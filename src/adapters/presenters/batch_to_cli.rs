@@ -0,0 +1,240 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    adapters::{
+        controllers::ProvenanceAdapter,
+        gateways::signals::InterruptFlag,
+        presenters::records::{
+            ErrorRecord,
+            InceptionRecord,
+            LastDeploymentRecord,
+            OutputFormat,
+            Record,
+        },
+    },
+    entities::SolceptionError,
+    use_cases::SolanaQueries,
+};
+
+/// Looks up many programs at once through one gateway, so they share its
+/// rate limiter and cache, and prints one row per program.  A program whose
+/// lookup fails gets an error row instead of failing the batch, unless the
+/// batch was interrupted.
+pub(crate) struct BatchToCli {
+    adapter: ProvenanceAdapter,
+    rpc_url: String,
+    output: OutputFormat,
+
+    /// The number of programs looked up at once.
+    jobs: usize,
+
+    /// Once requested, the programs not yet started fail as interrupted.
+    interrupt: InterruptFlag,
+}
+
+impl BatchToCli {
+    #[instrument(skip(solana))]
+    pub fn new_with_gateway(
+        solana: Box<dyn SolanaQueries>,
+        strict: bool,
+        rpc_url: String,
+        output: OutputFormat,
+        jobs: usize,
+        interrupt: InterruptFlag,
+    ) -> Result<Self> {
+        // Batches are printed as rows, so the default text output becomes
+        // CSV and JSON becomes one object per line.
+        let output = match output {
+            OutputFormat::Text | OutputFormat::Csv => OutputFormat::Csv,
+            OutputFormat::Json | OutputFormat::Ndjson => OutputFormat::Ndjson,
+            OutputFormat::Yaml => {
+                return Err(anyhow!(
+                    "Batch lookups print one row per program, which YAML cannot.  Use csv or \
+                     ndjson output instead."
+                ))
+            }
+        };
+
+        Ok(Self {
+            adapter: ProvenanceAdapter::new_with_gateway(solana, strict),
+            rpc_url,
+            output,
+            jobs: jobs.max(1),
+            interrupt,
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_provenance(&self, program_ids: &[String]) -> Result<String> {
+        debug!(
+            "Beginning batch provenance lookup of {} programs.",
+            program_ids.len()
+        );
+
+        self.lookup_each(program_ids, |program_id| {
+            let report = self.adapter.lookup_provenance(&program_id.parse()?)?;
+
+            Ok(InceptionRecord::new(report, &self.rpc_url))
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_last_deployment(&self, program_ids: &[String]) -> Result<String> {
+        debug!(
+            "Beginning batch last deployment lookup of {} programs.",
+            program_ids.len()
+        );
+
+        self.lookup_each(program_ids, |program_id| {
+            let last_deployment = self.adapter.lookup_last_deployment(&program_id.parse()?)?;
+
+            Ok(LastDeploymentRecord::new(last_deployment, &self.rpc_url))
+        })
+    }
+
+    fn lookup_each<R: Record + Send>(
+        &self,
+        program_ids: &[String],
+        lookup: impl Fn(&str) -> Result<R> + Sync,
+    ) -> Result<String> {
+        let results = self.run_concurrently(program_ids, lookup);
+
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        if failed > 0 {
+            warn!(
+                "{failed} of {} lookups failed.  Their rows carry the error instead of a result.",
+                results.len()
+            );
+        }
+        let interrupted =
+            results
+                .iter()
+                .find_map(|result| match result.as_ref().err()?.downcast_ref() {
+                    Some(SolceptionError::Interrupted {
+                        program_id,
+                        searched,
+                        ..
+                    }) => Some((program_id.clone(), *searched)),
+                    _ => None,
+                });
+
+        let output = self.render_rows(program_ids, results)?;
+
+        // The rows are printed even when the batch was interrupted, but the
+        // batch then fails as the lookup of its first interrupted program.
+        match interrupted {
+            Some((program_id, searched)) => Err(SolceptionError::Interrupted {
+                program_id,
+                searched,
+                partial_result: Some(output),
+            }
+            .into()),
+            None => Ok(output),
+        }
+    }
+
+    fn render_rows<R: Record>(
+        &self,
+        program_ids: &[String],
+        results: Vec<Result<R>>,
+    ) -> Result<String> {
+        let rows = program_ids.iter().zip(results);
+        match self.output {
+            OutputFormat::Csv => {
+                let header: Vec<&str> = R::csv_header()
+                    .iter()
+                    .copied()
+                    .chain(["error", "exit_code"])
+                    .collect();
+
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(&header)?;
+                for (program_id, result) in rows {
+                    match result {
+                        Ok(record) => {
+                            for row in record.csv_rows() {
+                                writer.write_record(
+                                    row.into_iter().chain([String::new(), String::new()]),
+                                )?;
+                            }
+                        }
+                        Err(e) => writer.write_record(
+                            ErrorRecord::new(program_id, &e, &self.rpc_url).csv_row(&header),
+                        )?,
+                    }
+                }
+                let csv = String::from_utf8(writer.into_inner()?)?;
+
+                Ok(csv.trim_end().to_string())
+            }
+            _ => {
+                let lines = rows
+                    .map(|(program_id, result)| match result {
+                        Ok(record) => record.render(OutputFormat::Ndjson),
+                        Err(e) => ErrorRecord::new(program_id, &e, &self.rpc_url)
+                            .render(OutputFormat::Ndjson),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+
+    /// Runs the lookups on a fixed number of workers, each taking the next
+    /// program in turn, and returns the results in the order of the programs.
+    /// Once an interrupt is requested, the programs not yet started fail as
+    /// interrupted rather than starting.
+    fn run_concurrently<R: Send>(
+        &self,
+        program_ids: &[String],
+        lookup: impl Fn(&str) -> Result<R> + Sync,
+    ) -> Vec<Result<R>> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(
+            program_ids
+                .iter()
+                .map(|_| None)
+                .collect::<Vec<Option<Result<R>>>>(),
+        );
+
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(program_ids.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(program_id) = program_ids.get(index) else {
+                        break;
+                    };
+
+                    let result = if self.interrupt.is_requested() {
+                        Err(SolceptionError::Interrupted {
+                            program_id: program_id.clone(),
+                            searched: 0,
+                            partial_result: None,
+                        }
+                        .into())
+                    } else {
+                        lookup(program_id)
+                    };
+                    results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .map(|result| result.expect("every program is claimed by exactly one worker"))
+            .collect()
+    }
+}
@@ -0,0 +1,91 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use tracing::{debug, instrument};
+
+use crate::adapters::gateways::cache::{cache_stats, clear_cache, prune_cache, ClusterStats};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Formats the results of the `cache` maintenance commands for CLI stdout.
+pub(crate) struct CacheToCli {
+    cache_dir: PathBuf,
+}
+
+impl CacheToCli {
+    #[instrument]
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+
+    #[instrument(skip(self))]
+    pub fn stats(&self) -> Result<String> {
+        let clusters = cache_stats(&self.cache_dir)?;
+        debug!("Found cached data for {} clusters.", clusters.len());
+
+        if clusters.is_empty() {
+            return Ok(format!(
+                "The cache at {} is empty.",
+                self.cache_dir.display()
+            ));
+        }
+
+        let mut lines = vec![format!("Cache: {}", self.cache_dir.display())];
+        lines.extend(clusters.iter().flat_map(cluster_lines));
+
+        Ok(lines.join("\n"))
+    }
+
+    #[instrument(skip(self))]
+    pub fn prune(&self, older_than_days: u64) -> Result<String> {
+        let freed = prune_cache(
+            &self.cache_dir,
+            Duration::from_secs(older_than_days.saturating_mul(SECONDS_PER_DAY)),
+        )?;
+
+        Ok(format!(
+            "Pruned {} of data older than {older_than_days} days from {}",
+            format_bytes(freed),
+            self.cache_dir.display()
+        ))
+    }
+
+    #[instrument(skip(self))]
+    pub fn clear(&self) -> Result<String> {
+        let freed = clear_cache(&self.cache_dir)?;
+
+        Ok(format!(
+            "Cleared {} from {}",
+            format_bytes(freed),
+            self.cache_dir.display()
+        ))
+    }
+}
+
+fn cluster_lines(cluster: &ClusterStats) -> Vec<String> {
+    vec![
+        String::new(),
+        format!("Cluster:      {}", cluster.genesis_hash),
+        format!("RPC nodes:    {}", cluster.rpc_urls.join(", ")),
+        format!("Histories:    {}", cluster.histories),
+        format!("Transactions: {}", cluster.transactions),
+        format!("Size:         {}", format_bytes(cluster.bytes)),
+    ]
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
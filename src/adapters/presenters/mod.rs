@@ -1,3 +1,9 @@
+mod batch_to_cli;
+mod cache_to_cli;
 mod provenance_to_cli;
+mod records;
 
+pub(crate) use batch_to_cli::BatchToCli;
+pub(crate) use cache_to_cli::CacheToCli;
 pub(crate) use provenance_to_cli::ProvenanceToCli;
+pub use records::OutputFormat;
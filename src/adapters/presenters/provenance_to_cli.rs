@@ -1,32 +1,231 @@
 use anyhow::Result;
 use tracing::{debug, instrument};
 
-use crate::{adapters::controllers::ProvenanceAdapter, use_cases::SolanaQueries};
+use crate::{
+    adapters::{
+        controllers::ProvenanceAdapter,
+        presenters::records::{
+            InceptionRecord,
+            LastDeploymentRecord,
+            OutputFormat,
+            Record,
+            TimelineRecord,
+        },
+    },
+    entities::{Deployment, DeploymentOutcome, SolceptionError},
+    use_cases::SolanaQueries,
+};
 
 pub(crate) struct ProvenanceToCli {
     adapter: ProvenanceAdapter,
+
+    /// The RPC node the gateway queries, recorded in structured output.
+    rpc_url: String,
+    output: OutputFormat,
 }
 
 impl ProvenanceToCli {
     #[instrument(skip(adapter))]
-    pub fn new(adapter: ProvenanceAdapter) -> Self {
-        Self { adapter }
+    pub fn new(adapter: ProvenanceAdapter, rpc_url: String, output: OutputFormat) -> Self {
+        Self {
+            adapter,
+            rpc_url,
+            output,
+        }
     }
 
     #[instrument(skip(solana))]
-    pub fn new_with_gateway(solana: Box<dyn SolanaQueries>) -> Self {
-        let adapter = ProvenanceAdapter::new_with_gateway(solana);
+    pub fn new_with_gateway(
+        solana: Box<dyn SolanaQueries>,
+        strict: bool,
+        rpc_url: String,
+        output: OutputFormat,
+    ) -> Self {
+        let adapter = ProvenanceAdapter::new_with_gateway(solana, strict);
 
-        Self::new(adapter)
+        Self::new(adapter, rpc_url, output)
     }
 
     #[instrument(skip(self))]
     pub fn lookup_provenance(&self, program_id: &str) -> Result<String> {
         debug!("Beginning program provenance via adapter lookup for {program_id}.");
 
-        let timestamp = self.adapter.lookup_provenance(program_id)?;
+        let report = self.adapter.lookup_provenance(&program_id.parse()?)?;
+        if self.output != OutputFormat::Text {
+            debug!(
+                "Provenance lookup complete.  Returning as {} for CLI stdout.",
+                self.output
+            );
+            return InceptionRecord::new(report, &self.rpc_url).render(self.output);
+        }
 
         debug!("Provenance lookup complete.  Returning as RFC 3339 timestamp for CLI stdout.");
-        Ok(timestamp.to_rfc3339())
+        let mut lines = vec![report
+            .inception
+            .block_time
+            .map(|block_time| block_time.to_rfc3339())
+            .unwrap_or_else(|| "unknown".to_string())];
+        lines.extend(failed_attempt_lines(&report.failed_attempts));
+        lines.extend(gap_lines(
+            &report.unrecoverable_signatures,
+            &report.undecoded_signatures,
+        ));
+
+        Ok(lines.join("\n"))
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_last_deployment(&self, program_id: &str) -> Result<String> {
+        debug!("Beginning last deployment via adapter lookup for {program_id}.");
+
+        let last_deployment = self.adapter.lookup_last_deployment(&program_id.parse()?)?;
+        if self.output != OutputFormat::Text {
+            debug!(
+                "Last deployment lookup complete.  Returning as {} for CLI stdout.",
+                self.output
+            );
+            return LastDeploymentRecord::new(last_deployment, &self.rpc_url).render(self.output);
+        }
+
+        debug!("Last deployment lookup complete.  Returning as RFC 3339 timestamp for CLI stdout.");
+        let mut lines = vec![last_deployment.block_time.to_rfc3339()];
+        if last_deployment.is_inception {
+            lines.push(
+                "Never upgraded: this deployment is also the program's inception.".to_string(),
+            );
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_timeline(&self, program_id: &str) -> Result<String> {
+        debug!("Beginning deployment timeline via adapter lookup for {program_id}.");
+
+        let timeline = self.adapter.lookup_timeline(&program_id.parse()?)?;
+        if self.output != OutputFormat::Text {
+            debug!(
+                "Deployment timeline lookup complete.  Returning as {} for CLI stdout.",
+                self.output
+            );
+            let interrupted_after = timeline.interrupted_after;
+            let program_id = timeline.program_id.to_string();
+            let output = TimelineRecord::new(timeline, &self.rpc_url).render(self.output)?;
+
+            return match interrupted_after {
+                Some(searched) => Err(SolceptionError::Interrupted {
+                    program_id,
+                    searched,
+                    partial_result: Some(output),
+                }
+                .into()),
+                None => Ok(output),
+            };
+        }
+
+        debug!("Deployment timeline lookup complete.  Returning as a table for CLI stdout.");
+        let mut lines = Vec::new();
+        if let Some(searched) = timeline.interrupted_after {
+            lines.push(format!(
+                "PARTIAL RESULT: the lookup was interrupted after searching {searched} \
+                 transactions, so deployments older than those listed may be missing."
+            ));
+            lines.push(String::new());
+        }
+        lines.extend([
+            format!("Program:     {}", timeline.program_id),
+            format!("ProgramData: {}", timeline.programdata_address),
+            String::new(),
+            timeline_header(),
+        ]);
+        lines.extend(timeline.deployments.iter().map(timeline_row));
+        lines.extend(failed_attempt_lines(&timeline.failed_attempts));
+        lines.extend(gap_lines(
+            &timeline.unrecoverable_signatures,
+            &timeline.undecoded_signatures,
+        ));
+
+        if let Some(searched) = timeline.interrupted_after {
+            return Err(SolceptionError::Interrupted {
+                program_id: timeline.program_id.to_string(),
+                searched,
+                partial_result: Some(lines.join("\n")),
+            }
+            .into());
+        }
+
+        Ok(lines.join("\n"))
     }
 }
+
+/// Tabulates the deployments that failed, with the error each failed with.
+fn failed_attempt_lines(failed_attempts: &[Deployment]) -> Vec<String> {
+    if failed_attempts.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = vec![
+        String::new(),
+        "Failed deployment attempts:".to_string(),
+        format!("{}  ERROR", timeline_header()),
+    ];
+    lines.extend(failed_attempts.iter().map(|attempt| {
+        let error = match &attempt.outcome {
+            DeploymentOutcome::Failed { error } => error.as_str(),
+            DeploymentOutcome::Succeeded => "-",
+        };
+        format!("{}  {}", timeline_row(attempt), error)
+    }));
+
+    lines
+}
+
+/// Lists the transactions whose details could not be retrieved or decoded,
+/// since any of them could hide a deployment missing from the result above.
+fn gap_lines(unrecoverable_signatures: &[String], undecoded_signatures: &[String]) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if !unrecoverable_signatures.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "Unrecoverable transactions ({}): their details could not be retrieved, so the result \
+             above may be incomplete.",
+            unrecoverable_signatures.len()
+        ));
+        lines.extend(unrecoverable_signatures.iter().cloned());
+    }
+
+    if !undecoded_signatures.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "Undecoded transactions ({}): their details could not be decoded, so the result above \
+             may be incomplete.",
+            undecoded_signatures.len()
+        ));
+        lines.extend(undecoded_signatures.iter().cloned());
+    }
+
+    lines
+}
+
+fn timeline_header() -> String {
+    format!(
+        "{:>12}  {:<25}  {:<13}  {:<88}  {:<44}  {:<44}",
+        "SLOT", "BLOCK TIME", "KIND", "SIGNATURE", "AUTHORITY", "FEE PAYER"
+    )
+}
+
+fn timeline_row(deployment: &Deployment) -> String {
+    format!(
+        "{:>12}  {:<25}  {:<13}  {:<88}  {:<44}  {:<44}",
+        deployment.slot,
+        deployment
+            .block_time
+            .map(|block_time| block_time.to_rfc3339())
+            .unwrap_or_else(|| "unknown".to_string()),
+        deployment.kind.to_string(),
+        deployment.signature,
+        deployment.authority.as_deref().unwrap_or("-"),
+        deployment.fee_payer,
+    )
+}
@@ -0,0 +1,440 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::prelude::*;
+use serde::Serialize;
+
+use crate::entities::{
+    Deployment,
+    DeploymentOutcome,
+    DeploymentTimeline,
+    LastDeployment,
+    ProvenanceReport,
+    SolceptionError,
+};
+
+/// The version of the structured output schema.  It changes only when a
+/// field is removed, renamed, or changes meaning; new fields may be added
+/// without a new version.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// The formats lookup results can be printed in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The RFC 3339 timestamp alone, or a table for the timeline.
+    #[default]
+    Text,
+
+    Json,
+
+    /// Compact JSON, one record per line.
+    Ndjson,
+    Yaml,
+
+    /// A header row followed by one row per result, or per deployment for
+    /// the timeline.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!(
+                "Unknown output format: {format} .  Use one of text, json, ndjson, yaml, or csv."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+        };
+
+        f.write_str(label)
+    }
+}
+
+/// A lookup result as printed in the structured formats.  Every record
+/// carries the schema version, which kind of result it is, and the RPC node
+/// it was looked up from.
+pub(crate) trait Record: Serialize {
+    fn csv_header() -> &'static [&'static str];
+
+    fn csv_rows(&self) -> Vec<Vec<String>>;
+
+    fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            OutputFormat::Ndjson => Ok(serde_json::to_string(self)?),
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(self)?.trim_end().to_string()),
+            OutputFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(Self::csv_header())?;
+                for row in self.csv_rows() {
+                    writer.write_record(row)?;
+                }
+                let csv = String::from_utf8(writer.into_inner()?)?;
+
+                Ok(csv.trim_end().to_string())
+            }
+            OutputFormat::Text => Err(anyhow!("Text output is not a structured record.")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct InceptionRecord {
+    schema_version: u32,
+    record_type: &'static str,
+    program_id: String,
+    programdata_address: String,
+    rpc_url: String,
+    slot: u64,
+    signature: String,
+    block_time: Option<String>,
+    block_time_unix: Option<i64>,
+
+    /// Whether every transaction searched was retrieved and decoded, so no
+    /// earlier deployment can be hiding in the history.
+    complete: bool,
+    unrecoverable_signatures: Vec<String>,
+    undecoded_signatures: Vec<String>,
+    failed_attempts: Vec<DeploymentRecord>,
+}
+
+impl InceptionRecord {
+    pub fn new(report: ProvenanceReport, rpc_url: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            record_type: "inception",
+            complete: report.is_complete(),
+            program_id: report.program_id.to_string(),
+            programdata_address: report.programdata_address.to_string(),
+            rpc_url: rpc_url.to_string(),
+            slot: report.inception.slot,
+            signature: report.inception.signature,
+            block_time: report
+                .inception
+                .block_time
+                .map(|block_time| block_time.to_rfc3339()),
+            block_time_unix: report
+                .inception
+                .block_time
+                .as_ref()
+                .map(DateTime::timestamp),
+            unrecoverable_signatures: report.unrecoverable_signatures,
+            undecoded_signatures: report.undecoded_signatures,
+            failed_attempts: report.failed_attempts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Record for InceptionRecord {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "schema_version",
+            "record_type",
+            "program_id",
+            "programdata_address",
+            "rpc_url",
+            "slot",
+            "signature",
+            "block_time",
+            "block_time_unix",
+            "complete",
+            "unrecoverable_signatures",
+            "undecoded_signatures",
+            "failed_attempt_signatures",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.schema_version.to_string(),
+            self.record_type.to_string(),
+            self.program_id.clone(),
+            self.programdata_address.clone(),
+            self.rpc_url.clone(),
+            self.slot.to_string(),
+            self.signature.clone(),
+            self.block_time.clone().unwrap_or_default(),
+            self.block_time_unix
+                .map(|block_time| block_time.to_string())
+                .unwrap_or_default(),
+            self.complete.to_string(),
+            self.unrecoverable_signatures.join(" "),
+            self.undecoded_signatures.join(" "),
+            self.failed_attempts
+                .iter()
+                .map(|attempt| attempt.signature.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        ]]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct LastDeploymentRecord {
+    schema_version: u32,
+    record_type: &'static str,
+    program_id: String,
+    programdata_address: String,
+    rpc_url: String,
+    slot: u64,
+    block_time: String,
+    block_time_unix: i64,
+    upgrade_authority: Option<String>,
+    is_inception: bool,
+}
+
+impl LastDeploymentRecord {
+    pub fn new(last_deployment: LastDeployment, rpc_url: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            record_type: "last_deployment",
+            program_id: last_deployment.program_id.to_string(),
+            programdata_address: last_deployment.programdata_address.to_string(),
+            rpc_url: rpc_url.to_string(),
+            slot: last_deployment.slot,
+            block_time: last_deployment.block_time.to_rfc3339(),
+            block_time_unix: last_deployment.block_time.timestamp(),
+            upgrade_authority: last_deployment.upgrade_authority,
+            is_inception: last_deployment.is_inception,
+        }
+    }
+}
+
+impl Record for LastDeploymentRecord {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "schema_version",
+            "record_type",
+            "program_id",
+            "programdata_address",
+            "rpc_url",
+            "slot",
+            "block_time",
+            "block_time_unix",
+            "upgrade_authority",
+            "is_inception",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.schema_version.to_string(),
+            self.record_type.to_string(),
+            self.program_id.clone(),
+            self.programdata_address.clone(),
+            self.rpc_url.clone(),
+            self.slot.to_string(),
+            self.block_time.clone(),
+            self.block_time_unix.to_string(),
+            self.upgrade_authority.clone().unwrap_or_default(),
+            self.is_inception.to_string(),
+        ]]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TimelineRecord {
+    schema_version: u32,
+    record_type: &'static str,
+    program_id: String,
+    programdata_address: String,
+    rpc_url: String,
+
+    /// Whether the whole history was searched and every transaction in it
+    /// retrieved and decoded.
+    complete: bool,
+    interrupted_after: Option<usize>,
+    unrecoverable_signatures: Vec<String>,
+    undecoded_signatures: Vec<String>,
+    deployments: Vec<DeploymentRecord>,
+    failed_attempts: Vec<DeploymentRecord>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeploymentRecord {
+    kind: String,
+    slot: u64,
+    signature: String,
+    block_time: Option<String>,
+    block_time_unix: Option<i64>,
+    authority: Option<String>,
+    fee_payer: String,
+    succeeded: bool,
+    error: Option<String>,
+}
+
+impl From<Deployment> for DeploymentRecord {
+    fn from(deployment: Deployment) -> Self {
+        let (succeeded, error) = match deployment.outcome {
+            DeploymentOutcome::Succeeded => (true, None),
+            DeploymentOutcome::Failed { error } => (false, Some(error)),
+        };
+
+        Self {
+            kind: deployment.kind.to_string(),
+            slot: deployment.slot,
+            signature: deployment.signature,
+            block_time: deployment
+                .block_time
+                .map(|block_time| block_time.to_rfc3339()),
+            block_time_unix: deployment.block_time.as_ref().map(DateTime::timestamp),
+            authority: deployment.authority,
+            fee_payer: deployment.fee_payer,
+            succeeded,
+            error,
+        }
+    }
+}
+
+impl TimelineRecord {
+    pub fn new(timeline: DeploymentTimeline, rpc_url: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            record_type: "timeline",
+            program_id: timeline.program_id.to_string(),
+            programdata_address: timeline.programdata_address.to_string(),
+            rpc_url: rpc_url.to_string(),
+            complete: timeline.is_complete(),
+            interrupted_after: timeline.interrupted_after,
+            unrecoverable_signatures: timeline.unrecoverable_signatures,
+            undecoded_signatures: timeline.undecoded_signatures,
+            deployments: timeline.deployments.into_iter().map(Into::into).collect(),
+            failed_attempts: timeline
+                .failed_attempts
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl Record for TimelineRecord {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "schema_version",
+            "record_type",
+            "program_id",
+            "programdata_address",
+            "rpc_url",
+            "complete",
+            "kind",
+            "slot",
+            "signature",
+            "block_time",
+            "block_time_unix",
+            "authority",
+            "fee_payer",
+            "succeeded",
+            "error",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.deployments
+            .iter()
+            .chain(&self.failed_attempts)
+            .map(|deployment| {
+                vec![
+                    self.schema_version.to_string(),
+                    self.record_type.to_string(),
+                    self.program_id.clone(),
+                    self.programdata_address.clone(),
+                    self.rpc_url.clone(),
+                    self.complete.to_string(),
+                    deployment.kind.clone(),
+                    deployment.slot.to_string(),
+                    deployment.signature.clone(),
+                    deployment.block_time.clone().unwrap_or_default(),
+                    deployment
+                        .block_time_unix
+                        .map(|block_time| block_time.to_string())
+                        .unwrap_or_default(),
+                    deployment.authority.clone().unwrap_or_default(),
+                    deployment.fee_payer.clone(),
+                    deployment.succeeded.to_string(),
+                    deployment.error.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Stands in for the result of one program in a batch whose lookup failed,
+/// so every program still gets a row.
+#[derive(Debug, Serialize)]
+pub(crate) struct ErrorRecord {
+    schema_version: u32,
+    record_type: &'static str,
+    program_id: String,
+    rpc_url: String,
+    error: String,
+
+    /// The exit code `solc` would have exited with for this program alone.
+    exit_code: i32,
+}
+
+impl ErrorRecord {
+    pub fn new(program_id: &str, error: &anyhow::Error, rpc_url: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            record_type: "error",
+            program_id: program_id.to_string(),
+            rpc_url: rpc_url.to_string(),
+            error: error.to_string(),
+            exit_code: error
+                .downcast_ref::<SolceptionError>()
+                .map_or(1, SolceptionError::exit_code),
+        }
+    }
+
+    /// Lays the error out under the header of the results it stands in for,
+    /// filling the columns they share and leaving the rest empty.
+    pub fn csv_row(&self, header: &[&str]) -> Vec<String> {
+        header
+            .iter()
+            .map(|column| match *column {
+                "schema_version" => self.schema_version.to_string(),
+                "record_type" => self.record_type.to_string(),
+                "program_id" => self.program_id.clone(),
+                "rpc_url" => self.rpc_url.clone(),
+                "error" => self.error.clone(),
+                "exit_code" => self.exit_code.to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+}
+
+impl Record for ErrorRecord {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "schema_version",
+            "record_type",
+            "program_id",
+            "rpc_url",
+            "error",
+            "exit_code",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        vec![self.csv_row(Self::csv_header())]
+    }
+}
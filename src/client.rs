@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::{instrument, trace};
+
+use crate::{
+    adapters::{
+        controllers::ProvenanceAdapter,
+        gateways::{
+            config::{resolve_rpc_settings, LookupOptions},
+            signals::InterruptFlag,
+            solana::{RecordingSender, ReplaySender, SolanaRpc, ThrottledHttpSender},
+        },
+    },
+    entities::{DeploymentTimeline, LastDeployment, ProgramId, ProvenanceReport},
+    use_cases::SolanaQueries,
+};
+
+/// An embeddable provenance client, for Rust programs that would rather call
+/// the lookups directly than run `solc`.
+///
+/// Results are the domain types themselves.  Failures are [`anyhow::Error`]s
+/// that downcast to [`crate::SolceptionError`] whenever the cause is one of
+/// its variants.  The client never installs a tracing subscriber or a
+/// Ctrl-C handler; both are left to the embedding program.
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let solception = solception::Solception::builder()
+///     .rpc_url("mainnet-beta")
+///     .build()?;
+/// let program_id = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb".parse()?;
+/// let report = solception.lookup_provenance(&program_id)?;
+/// println!("{:?}", report.inception.block_time);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Solception {
+    adapter: ProvenanceAdapter,
+}
+
+impl Solception {
+    pub fn builder() -> SolceptionBuilder {
+        SolceptionBuilder::default()
+    }
+
+    /// Finds the initial deployment of the program.
+    #[instrument(skip(self))]
+    pub fn lookup_provenance(&self, program_id: &ProgramId) -> Result<ProvenanceReport> {
+        self.adapter.lookup_provenance(program_id)
+    }
+
+    /// Finds the most recent deploy or upgrade of the program.
+    #[instrument(skip(self))]
+    pub fn lookup_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment> {
+        self.adapter.lookup_last_deployment(program_id)
+    }
+
+    /// Lists every deployment of the program, ordered by slot.
+    #[instrument(skip(self))]
+    pub fn lookup_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline> {
+        self.adapter.lookup_timeline(program_id)
+    }
+}
+
+/// Configures a [`Solception`] client.  Settings left unset resolve the same
+/// way as for `solc`: the solc config file, then the Solana CLI config file,
+/// then the built-in defaults.  A gateway supplied with
+/// [`SolceptionBuilder::gateway`] replaces the RPC gateway, and with it every
+/// RPC, cache, and recording setting.
+#[derive(Default)]
+pub struct SolceptionBuilder {
+    options: LookupOptions,
+    gateway: Option<Box<dyn SolanaQueries>>,
+}
+
+impl SolceptionBuilder {
+    /// Starts from a complete set of options, such as those parsed by a
+    /// command line.
+    pub fn options(mut self, options: LookupOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// RPC node URL or cluster moniker (`m`, `d`, `t`, `l`, or their full
+    /// names).
+    pub fn rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.options.url = Some(url.into());
+        self
+    }
+
+    /// Commitment level for RPC queries, e.g. `confirmed` or `finalized`.
+    pub fn commitment(mut self, commitment: impl Into<String>) -> Self {
+        self.options.commitment = Some(commitment.into());
+        self
+    }
+
+    /// Timeout for each request to the RPC node, in whole seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout.as_secs());
+        self
+    }
+
+    pub fn config_file(mut self, config_file: impl Into<String>) -> Self {
+        self.options.config_file = Some(config_file.into());
+        self
+    }
+
+    pub fn requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.options.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.options.concurrency = Some(concurrency);
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    pub fn cache_dir(mut self, cache_dir: impl Into<String>) -> Self {
+        self.options.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.options.no_cache = no_cache;
+        self
+    }
+
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.options.offline = offline;
+        self
+    }
+
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.options.resume = resume;
+        self
+    }
+
+    pub fn record_file(mut self, record_file: impl Into<String>) -> Self {
+        self.options.record_file = Some(record_file.into());
+        self
+    }
+
+    pub fn replay_file(mut self, replay_file: impl Into<String>) -> Self {
+        self.options.replay_file = Some(replay_file.into());
+        self
+    }
+
+    /// Stops the history scans of every lookup at their next checkpoint once
+    /// the flag is requested, returning `Interrupted` with the progress
+    /// saved.  Clear the flag before running more lookups.
+    pub fn interrupt(mut self, interrupt: InterruptFlag) -> Self {
+        self.options.interrupt = interrupt;
+        self
+    }
+
+    /// Answers every query from the given gateway instead of an RPC node.
+    pub fn gateway(mut self, gateway: impl SolanaQueries + 'static) -> Self {
+        self.gateway = Some(Box::new(gateway));
+        self
+    }
+
+    #[instrument(skip(self))]
+    pub fn build(self) -> Result<Solception> {
+        let gateway = match self.gateway {
+            Some(gateway) => gateway,
+            None => Box::new(build_gateway(&self.options)?),
+        };
+        let adapter = ProvenanceAdapter::new_with_gateway(gateway, self.options.strict);
+        trace!("Solception client built.");
+
+        Ok(Solception { adapter })
+    }
+}
+
+/// Builds the RPC gateway described by the options, with its cache and
+/// checkpoints attached.
+#[instrument]
+pub(crate) fn build_gateway(options: &LookupOptions) -> Result<SolanaRpc> {
+    let settings = resolve_rpc_settings(options)?;
+    let mut solana = match (&settings.replay_file, &settings.record_file) {
+        (Some(replay_file), _) => SolanaRpc::new_with_sender(
+            ReplaySender::load(replay_file)?,
+            settings.commitment,
+            settings.concurrency,
+        )?,
+        (None, record_file) => {
+            let sender = ThrottledHttpSender::new(
+                &settings.json_rpc_url,
+                settings.timeout,
+                settings.requests_per_second,
+                settings.concurrency,
+            );
+            match record_file {
+                Some(record_file) => SolanaRpc::new_with_sender(
+                    RecordingSender::create(sender, record_file)?,
+                    settings.commitment,
+                    settings.concurrency,
+                )?,
+                None => {
+                    SolanaRpc::new_with_sender(sender, settings.commitment, settings.concurrency)?
+                }
+            }
+        }
+    };
+    if let Some(cache_dir) = &settings.cache_dir {
+        solana.attach_cache(cache_dir, settings.offline);
+    }
+    if let Some(checkpoint_dir) = &settings.checkpoint_dir {
+        solana.attach_checkpoints(checkpoint_dir, settings.resume);
+    }
+    solana.attach_interrupt(options.interrupt.clone());
+
+    Ok(solana)
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{ProgramDataAddress, ProgramId};
+
+/// The kinds of loader activity that change what code a program runs or who
+/// may change it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeploymentKind {
+    InitialDeploy,
+    Upgrade,
+    SetAuthority,
+    ExtendProgram,
+    Close,
+    Migrate,
+    Retract,
+    Finalize,
+}
+
+impl fmt::Display for DeploymentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            DeploymentKind::InitialDeploy => "deploy",
+            DeploymentKind::Upgrade => "upgrade",
+            DeploymentKind::SetAuthority => "set-authority",
+            DeploymentKind::ExtendProgram => "extend",
+            DeploymentKind::Close => "close",
+            DeploymentKind::Migrate => "migrate",
+            DeploymentKind::Retract => "retract",
+            DeploymentKind::Finalize => "finalize",
+        };
+
+        f.write_str(label)
+    }
+}
+
+/// Whether the transaction carrying a loader instruction was committed
+/// successfully.  Failed transactions change nothing on chain, but repeated
+/// failed deploys are still useful forensic signal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeploymentOutcome {
+    Succeeded,
+    Failed { error: String },
+}
+
+/// A single loader instruction applied to a program, along with the
+/// transaction that carried it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub kind: DeploymentKind,
+    pub slot: u64,
+    pub signature: String,
+    pub block_time: Option<DateTime<Utc>>,
+
+    /// The authority that signed the loader instruction, when it names one.
+    pub authority: Option<String>,
+    pub fee_payer: String,
+    pub outcome: DeploymentOutcome,
+}
+
+impl Deployment {
+    pub fn succeeded(&self) -> bool {
+        self.outcome == DeploymentOutcome::Succeeded
+    }
+}
+
+/// Every deployment of a program, ordered by slot.  Loader instructions from
+/// failed transactions are kept apart so they never count as a deployment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentTimeline {
+    pub program_id: ProgramId,
+    pub programdata_address: ProgramDataAddress,
+    pub deployments: Vec<Deployment>,
+    pub failed_attempts: Vec<Deployment>,
+
+    /// Transactions in the history whose details could not be retrieved
+    /// even after retrying, so the timeline may be missing entries.
+    pub unrecoverable_signatures: Vec<String>,
+
+    /// Transactions in the history that were retrieved but could not be
+    /// decoded, so the timeline may be missing entries.
+    pub undecoded_signatures: Vec<String>,
+
+    /// The number of transactions searched before the lookup was
+    /// interrupted, when it was, in which case the timeline is partial.
+    pub interrupted_after: Option<usize>,
+}
+
+impl DeploymentTimeline {
+    /// Whether the whole history was searched and every transaction in it
+    /// retrieved and decoded, so no deployment can be missing.
+    pub fn is_complete(&self) -> bool {
+        self.interrupted_after.is_none()
+            && self.unrecoverable_signatures.is_empty()
+            && self.undecoded_signatures.is_empty()
+    }
+}
@@ -0,0 +1,91 @@
+use thiserror::Error;
+
+/// The reasons a lookup can fail, each mapped to a stable process exit code
+/// so scripts can branch on the cause instead of parsing the message.
+///
+/// | Code | Reason                                          |
+/// |------|-------------------------------------------------|
+/// | 0    | Success                                         |
+/// | 1    | Any other failure, such as an unreadable config |
+/// | 2    | Invalid command-line usage                      |
+/// | 3    | [`SolceptionError::InvalidPubkey`]              |
+/// | 4    | [`SolceptionError::NotAProgram`]                |
+/// | 5    | [`SolceptionError::NoDeploymentsFound`]         |
+/// | 6    | [`SolceptionError::HistoryTruncated`]           |
+/// | 7    | [`SolceptionError::RpcTransport`]               |
+/// | 8    | [`SolceptionError::RateLimited`]                |
+/// | 9    | [`SolceptionError::DecodeFailure`]              |
+/// | 10   | [`SolceptionError::NotCached`]                  |
+/// | 130  | [`SolceptionError::Interrupted`]                |
+#[derive(Debug, Error)]
+pub enum SolceptionError {
+    #[error(
+        "Failed to parse program_id: {input} ({reason}).  Most likely the provided value is not a \
+         base 58 public key.  Check the input against a blockchain explorer."
+    )]
+    InvalidPubkey { input: String, reason: String },
+
+    #[error("The account {address} is not a deployed program: {reason}")]
+    NotAProgram { address: String, reason: String },
+
+    #[error(
+        "No deployment found in the history of program_id: {program_id}.  Most likely this is an \
+         error in input for the address or the network.  Check the program ID on a blockchain \
+         explorer to verify that it is valid on the chosen network."
+    )]
+    NoDeploymentsFound { program_id: String },
+
+    #[error(
+        "The history of program_id: {program_id} is incomplete: {reason}.  The chosen RPC node \
+         may be missing historical data, or network issues prevented retrieval of some \
+         transaction details.  Retry later, or against an RPC node with full history."
+    )]
+    HistoryTruncated { program_id: String, reason: String },
+
+    #[error("The request to the RPC node failed: {reason}")]
+    RpcTransport { reason: String },
+
+    #[error(
+        "The RPC node rate limited the lookup: {reason}.  Retry later, or against an RPC node \
+         with higher rate limits."
+    )]
+    RateLimited { reason: String },
+
+    #[error("Failed to decode {what}: {reason}")]
+    DecodeFailure { what: String, reason: String },
+
+    #[error(
+        "Offline mode makes no requests to the RPC node, and the cache holds no {what}.  Run the \
+         lookup once without `--offline` to populate the cache."
+    )]
+    NotCached { what: String },
+
+    /// Carries whatever result was found before the interruption, already
+    /// marked as partial, for the caller to print.
+    #[error(
+        "The lookup of program_id: {program_id} was interrupted after searching {searched} \
+         transactions.  Run it again with `--resume` to continue from where it stopped."
+    )]
+    Interrupted {
+        program_id: String,
+        searched: usize,
+        partial_result: Option<String>,
+    },
+}
+
+impl SolceptionError {
+    /// The process exit code documented for this failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SolceptionError::InvalidPubkey { .. } => 3,
+            SolceptionError::NotAProgram { .. } => 4,
+            SolceptionError::NoDeploymentsFound { .. } => 5,
+            SolceptionError::HistoryTruncated { .. } => 6,
+            SolceptionError::RpcTransport { .. } => 7,
+            SolceptionError::RateLimited { .. } => 8,
+            SolceptionError::DecodeFailure { .. } => 9,
+            SolceptionError::NotCached { .. } => 10,
+            SolceptionError::Interrupted { .. } => 130,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{ProgramDataAddress, ProgramId};
+
+/// The most recent deploy or upgrade of an upgradeable program, as recorded
+/// in the `slot` field of its ProgramData account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastDeployment {
+    pub program_id: ProgramId,
+    pub programdata_address: ProgramDataAddress,
+    pub slot: u64,
+    pub block_time: DateTime<Utc>,
+    pub upgrade_authority: Option<String>,
+
+    /// Set when the ProgramData history shows no earlier successful
+    /// transaction, meaning the program has never been upgraded and this
+    /// deployment is also its inception.
+    pub is_inception: bool,
+}
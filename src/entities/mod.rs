@@ -1 +1,13 @@
+mod deployment;
+mod error;
+mod last_deployment;
+mod program_id;
+mod programdata_address;
+mod provenance_report;
 
+pub use deployment::{Deployment, DeploymentKind, DeploymentOutcome, DeploymentTimeline};
+pub use error::SolceptionError;
+pub use last_deployment::LastDeployment;
+pub use program_id::ProgramId;
+pub use programdata_address::ProgramDataAddress;
+pub use provenance_report::ProvenanceReport;
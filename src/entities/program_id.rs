@@ -0,0 +1,57 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::entities::SolceptionError;
+
+/// The address of a program account, the subject of every lookup.  Parsing
+/// one is where a caller's input is checked to be a public key at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProgramId(Pubkey);
+
+impl ProgramId {
+    pub fn pubkey(&self) -> Pubkey {
+        self.0
+    }
+}
+
+impl From<Pubkey> for ProgramId {
+    fn from(pubkey: Pubkey) -> Self {
+        Self(pubkey)
+    }
+}
+
+impl FromStr for ProgramId {
+    type Err = SolceptionError;
+
+    fn from_str(program_id: &str) -> Result<Self, Self::Err> {
+        Pubkey::from_str(program_id)
+            .map(Self)
+            .map_err(|e| SolceptionError::InvalidPubkey {
+                input: program_id.to_string(),
+                reason: e.to_string(),
+            })
+    }
+}
+
+impl TryFrom<String> for ProgramId {
+    type Error = SolceptionError;
+
+    fn try_from(program_id: String) -> Result<Self, Self::Error> {
+        program_id.parse()
+    }
+}
+
+impl From<ProgramId> for String {
+    fn from(program_id: ProgramId) -> Self {
+        program_id.to_string()
+    }
+}
+
+impl fmt::Display for ProgramId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
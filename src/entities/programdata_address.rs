@@ -0,0 +1,58 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::entities::SolceptionError;
+
+/// The account whose transaction history records a program's deployments:
+/// the ProgramData account of an upgradeable program, or the program account
+/// itself otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProgramDataAddress(Pubkey);
+
+impl ProgramDataAddress {
+    pub fn pubkey(&self) -> Pubkey {
+        self.0
+    }
+}
+
+impl From<Pubkey> for ProgramDataAddress {
+    fn from(pubkey: Pubkey) -> Self {
+        Self(pubkey)
+    }
+}
+
+impl FromStr for ProgramDataAddress {
+    type Err = SolceptionError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        Pubkey::from_str(address)
+            .map(Self)
+            .map_err(|e| SolceptionError::DecodeFailure {
+                what: format!("the ProgramData address {address}"),
+                reason: e.to_string(),
+            })
+    }
+}
+
+impl TryFrom<String> for ProgramDataAddress {
+    type Error = SolceptionError;
+
+    fn try_from(address: String) -> Result<Self, Self::Error> {
+        address.parse()
+    }
+}
+
+impl From<ProgramDataAddress> for String {
+    fn from(address: ProgramDataAddress) -> Self {
+        address.to_string()
+    }
+}
+
+impl fmt::Display for ProgramDataAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
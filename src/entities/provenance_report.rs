@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Deployment, ProgramDataAddress, ProgramId};
+
+/// The provenance of a program: the transaction that first deployed it,
+/// found by searching its history, and how complete that search was.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceReport {
+    pub program_id: ProgramId,
+    pub programdata_address: ProgramDataAddress,
+
+    /// The successful initial deploy, the oldest deployment in the history.
+    pub inception: Deployment,
+
+    /// The deployments that failed before the inception, oldest first.
+    pub failed_attempts: Vec<Deployment>,
+
+    /// Transactions in the history whose details could not be retrieved
+    /// even after retrying.  Any of them could hold an earlier deployment.
+    pub unrecoverable_signatures: Vec<String>,
+
+    /// Transactions in the history that were retrieved but could not be
+    /// decoded.  Any of them could hold an earlier deployment too.
+    pub undecoded_signatures: Vec<String>,
+}
+
+impl ProvenanceReport {
+    /// Whether every transaction searched was retrieved and decoded, so no
+    /// earlier deployment can be hiding in the history.
+    pub fn is_complete(&self) -> bool {
+        self.unrecoverable_signatures.is_empty() && self.undecoded_signatures.is_empty()
+    }
+}
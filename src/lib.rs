@@ -1,23 +1,160 @@
 mod adapters;
+mod client;
 mod entities;
 mod use_cases;
 
 use anyhow::Result;
 use tracing::{instrument, trace};
 
-use crate::adapters::{
-    gateways::{solana::SolanaRpc, telemetry::init_tracing},
-    presenters::ProvenanceToCli,
+use crate::{
+    adapters::{
+        gateways::config::resolve_cache_dir,
+        presenters::{BatchToCli, CacheToCli, ProvenanceToCli},
+    },
+    client::build_gateway,
 };
+pub use crate::{
+    adapters::{
+        gateways::{
+            config::LookupOptions,
+            signals::{install_interrupt_handler, InterruptFlag},
+            telemetry::{init_tracing, LogFormat, TracingOptions},
+        },
+        presenters::OutputFormat,
+    },
+    client::{Solception, SolceptionBuilder},
+    entities::{
+        Deployment,
+        DeploymentKind,
+        DeploymentOutcome,
+        DeploymentTimeline,
+        LastDeployment,
+        ProgramDataAddress,
+        ProgramId,
+        ProvenanceReport,
+        SolceptionError,
+    },
+    use_cases::SolanaQueries,
+};
+
+#[instrument]
+pub fn lookup_provenance(
+    options: &LookupOptions,
+    output: OutputFormat,
+    program_id: &str,
+) -> Result<String> {
+    let presenter = bootstrap(options, output)?;
+
+    presenter.lookup_provenance(program_id)
+}
 
 #[instrument]
-pub fn lookup_provenance(debug_level: u8, node_url: &str, program_id: &str) -> Result<String> {
-    init_tracing(debug_level)?;
+pub fn lookup_last_deployment(
+    options: &LookupOptions,
+    output: OutputFormat,
+    program_id: &str,
+) -> Result<String> {
+    let presenter = bootstrap(options, output)?;
+
+    presenter.lookup_last_deployment(program_id)
+}
+
+#[instrument]
+pub fn lookup_timeline(
+    options: &LookupOptions,
+    output: OutputFormat,
+    program_id: &str,
+) -> Result<String> {
+    let presenter = bootstrap(options, output)?;
+
+    presenter.lookup_timeline(program_id)
+}
+
+/// Looks up the inception of every program, a few at a time, and prints one
+/// CSV or NDJSON row per program.
+#[instrument]
+pub fn lookup_provenance_batch(
+    options: &LookupOptions,
+    output: OutputFormat,
+    jobs: usize,
+    program_ids: &[String],
+) -> Result<String> {
+    let presenter = bootstrap_batch(options, output, jobs)?;
+
+    presenter.lookup_provenance(program_ids)
+}
+
+/// Looks up the last deployment of every program, a few at a time, and
+/// prints one CSV or NDJSON row per program.
+#[instrument]
+pub fn lookup_last_deployment_batch(
+    options: &LookupOptions,
+    output: OutputFormat,
+    jobs: usize,
+    program_ids: &[String],
+) -> Result<String> {
+    let presenter = bootstrap_batch(options, output, jobs)?;
+
+    presenter.lookup_last_deployment(program_ids)
+}
+
+#[instrument]
+pub fn cache_stats(options: &LookupOptions) -> Result<String> {
+    let presenter = bootstrap_cache(options)?;
+
+    presenter.stats()
+}
+
+#[instrument]
+pub fn prune_cache(options: &LookupOptions, older_than_days: u64) -> Result<String> {
+    let presenter = bootstrap_cache(options)?;
+
+    presenter.prune(older_than_days)
+}
+
+#[instrument]
+pub fn clear_cache(options: &LookupOptions) -> Result<String> {
+    let presenter = bootstrap_cache(options)?;
+
+    presenter.clear()
+}
+
+fn bootstrap(options: &LookupOptions, output: OutputFormat) -> Result<ProvenanceToCli> {
     trace!("Entering library bootstrap path.");
 
-    let solana = SolanaRpc::new_with_url(node_url);
-    let presenter = ProvenanceToCli::new_with_gateway(Box::new(solana));
+    let solana = build_gateway(options)?;
+    let rpc_url = solana.url();
+    let presenter =
+        ProvenanceToCli::new_with_gateway(Box::new(solana), options.strict, rpc_url, output);
     trace!("Bootstrap complete.  Forwarding to presenter.");
 
-    presenter.lookup_provenance(program_id)
+    Ok(presenter)
+}
+
+fn bootstrap_batch(
+    options: &LookupOptions,
+    output: OutputFormat,
+    jobs: usize,
+) -> Result<BatchToCli> {
+    trace!("Entering batch bootstrap path.");
+
+    let solana = build_gateway(options)?;
+    let rpc_url = solana.url();
+    let presenter = BatchToCli::new_with_gateway(
+        Box::new(solana),
+        options.strict,
+        rpc_url,
+        output,
+        jobs,
+        options.interrupt.clone(),
+    )?;
+    trace!("Bootstrap complete.  Forwarding to batch presenter.");
+
+    Ok(presenter)
+}
+
+fn bootstrap_cache(options: &LookupOptions) -> Result<CacheToCli> {
+    trace!("Entering cache maintenance bootstrap path.");
+
+    Ok(CacheToCli::new(resolve_cache_dir(options)?))
 }
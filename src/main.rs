@@ -1,31 +1,344 @@
-use clap::{ArgAction, Parser};
-use solception::lookup_provenance;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+};
 
-const PUBLIC_DEVNET_RPC_NODE_URL: &str = "https://api.devnet.solana.com";
+use anyhow::{anyhow, Result};
+use clap::{ArgAction, Parser, Subcommand};
+use solception::{
+    cache_stats,
+    clear_cache,
+    init_tracing,
+    install_interrupt_handler,
+    lookup_last_deployment,
+    lookup_last_deployment_batch,
+    lookup_provenance,
+    lookup_provenance_batch,
+    lookup_timeline,
+    prune_cache,
+    InterruptFlag,
+    LogFormat,
+    LookupOptions,
+    OutputFormat,
+    SolceptionError,
+    TracingOptions,
+};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about)]
+#[command(version, about, long_about, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Required argument for the target program on Solana in the form of its
-    /// base 58 public key.
-    program_id: String,
+    /// base 58 public key.  Give more than one, or `-` to read them from
+    /// stdin, to look them all up as a batch.
+    #[arg(value_name = "PROGRAM_ID", required_unless_present = "input")]
+    program_ids: Vec<String>,
+
+    /// Optional file of program IDs to look up as a batch, one per line, or
+    /// `-` for stdin.  Blank lines and lines starting with `#` are skipped.
+    #[arg(long, value_name = "FILEPATH")]
+    input: Option<String>,
+
+    /// Optional number of programs a batch looks up at once.  They all share
+    /// one request budget and cache.
+    #[arg(long, value_name = "PROGRAMS", env = "SOLC_JOBS", default_value_t = 4)]
+    jobs: usize,
+
+    /// Optional URL for the Solana JSON RPC node, or a cluster moniker:
+    /// [mainnet-beta, testnet, devnet, localhost] or their first letter.
+    /// Falls back to the solc config file, then the Solana CLI config file,
+    /// then the public devnet RPC node.
+    #[arg(
+        short = 'u',
+        long = "url",
+        value_name = "URL_OR_MONIKER",
+        env = "SOLC_RPC_URL",
+        global = true
+    )]
+    url: Option<String>,
+
+    /// Optional timeout in seconds for each request to the RPC node.
+    #[arg(long, value_name = "SECONDS", env = "SOLC_TIMEOUT", global = true)]
+    timeout: Option<u64>,
+
+    /// Optional commitment level for RPC queries: [confirmed, finalized].
+    /// Falls back to the solc config file, then the Solana CLI config file,
+    /// then `finalized`.
+    #[arg(
+        long,
+        value_name = "COMMITMENT_LEVEL",
+        env = "SOLC_COMMITMENT",
+        global = true
+    )]
+    commitment: Option<String>,
+
+    /// Optional budget of requests per second sent to the RPC node.  Falls
+    /// back to the solc config file, then 10, the limit of the public RPC
+    /// nodes.
+    #[arg(long, value_name = "REQUESTS", env = "SOLC_RPS", global = true)]
+    rps: Option<u32>,
+
+    /// Optional maximum number of requests in flight to the RPC node at
+    /// once.  Falls back to the solc config file, then 10.
+    #[arg(long, value_name = "REQUESTS", env = "SOLC_CONCURRENCY", global = true)]
+    concurrency: Option<usize>,
+
+    /// Optional path to a solc config file.  Defaults to
+    /// `~/.config/solc/config.yml` when present.
+    #[arg(long, value_name = "FILEPATH", env = "SOLC_CONFIG", global = true)]
+    config: Option<String>,
+
+    /// Fail instead of returning a result when the details of any
+    /// transaction in the program's history could not be retrieved.
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Optional directory of the local transaction cache.  Falls back to the
+    /// solc config file, then `solc` in the platform's cache directory.
+    #[arg(long, value_name = "DIRECTORY", env = "SOLC_CACHE_DIR", global = true)]
+    cache_dir: Option<String>,
+
+    /// Neither read from nor write to the local transaction cache.
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Answer from the local transaction cache alone, without contacting the
+    /// RPC node.
+    #[arg(long, global = true, conflicts_with = "no_cache")]
+    offline: bool,
+
+    /// Optional file to record every JSON-RPC request to the RPC node and
+    /// its response to, for replaying later with `--replay`.
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        env = "SOLC_RECORD",
+        global = true,
+        conflicts_with = "replay"
+    )]
+    record: Option<String>,
+
+    /// Optional file of JSON-RPC exchanges recorded with `--record` to
+    /// answer from, without any network access.
+    #[arg(long, value_name = "FILEPATH", env = "SOLC_REPLAY", global = true)]
+    replay: Option<String>,
+
+    /// Continue an interrupted history search from the checkpoint it saved,
+    /// instead of starting over.
+    #[arg(long, global = true)]
+    resume: bool,
+
+    /// Optional format of the printed result: [text, json, ndjson, yaml,
+    /// csv].  The structured formats follow a versioned schema, described in
+    /// the README.  Batches of several programs print one row per program,
+    /// as csv for text and as ndjson for json, and cannot be printed as yaml.
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        env = "SOLC_OUTPUT",
+        default_value_t = OutputFormat::Text,
+        global = true
+    )]
+    output: OutputFormat,
+
+    /// Report when the program was last deployed or upgraded, read directly
+    /// from its ProgramData account instead of searching its history.  When
+    /// the program was never upgraded, a second line notes that this
+    /// deployment is also its inception.
+    #[arg(long)]
+    last_deployed: bool,
 
     /// Optional logging verbosity level. Repeat up to four times to increase
     /// verbosity.
-    #[arg(short, long, action=ArgAction::Count)]
+    #[arg(short, long, action=ArgAction::Count, global = true)]
     verbose: u8,
+
+    /// Optional `RUST_LOG`-style directives selecting log events by module
+    /// and level, e.g. `solception=debug,hyper=off`.  Events no directive
+    /// matches are logged at the `--verbose` level.
+    #[arg(long, value_name = "DIRECTIVES", env = "RUST_LOG", global = true)]
+    log_filter: Option<String>,
+
+    /// Optional layout of log events: [pretty, compact, json].
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        env = "SOLC_LOG_FORMAT",
+        default_value_t = LogFormat::Pretty,
+        global = true
+    )]
+    log_format: LogFormat,
+
+    /// Optional file to append log events to instead of stderr.  Stdout only
+    /// ever carries results.
+    #[arg(long, value_name = "FILEPATH", env = "SOLC_LOG_FILE", global = true)]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List every deploy, upgrade, authority change, extension, and close of
+    /// the program, ordered by slot.
+    Timeline {
+        /// Required argument for the target program on Solana in the form of
+        /// its base 58 public key.
+        program_id: String,
+    },
+
+    /// Inspect or remove the local transaction cache.
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Summarize the cached histories and transactions of each cluster.
+    Stats,
+
+    /// Remove cached histories and transactions that have not been written
+    /// recently.
+    Prune {
+        /// Remove data not written for this many days.
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        older_than: u64,
+    },
+
+    /// Remove everything in the cache.
+    Clear,
 }
 
 fn main() {
+    dotenvy::dotenv().ok();
+
     let cli = Cli::parse();
 
-    let timestamp = lookup_provenance(cli.verbose, PUBLIC_DEVNET_RPC_NODE_URL, &cli.program_id)
-        .unwrap_or_else(|err| {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
-        });
+    let tracing_options = TracingOptions {
+        debug_level: cli.verbose,
+        filter: cli.log_filter,
+        format: cli.log_format,
+        log_file: cli.log_file,
+    };
+    if let Err(err) = init_tracing(&tracing_options) {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+
+    let interrupt = InterruptFlag::default();
+    install_interrupt_handler(interrupt.clone());
+
+    let options = LookupOptions {
+        url: cli.url,
+        commitment: cli.commitment,
+        timeout: cli.timeout,
+        config_file: cli.config,
+        requests_per_second: cli.rps,
+        concurrency: cli.concurrency,
+        strict: cli.strict,
+        cache_dir: cli.cache_dir,
+        no_cache: cli.no_cache,
+        offline: cli.offline,
+        resume: cli.resume,
+        record_file: cli.record,
+        replay_file: cli.replay,
+        interrupt,
+    };
 
-    println!("{}", timestamp);
+    let is_batch = cli.input.is_some() || cli.program_ids.len() > 1 || cli.program_ids == ["-"];
+
+    let result = match (cli.command, cli.program_ids.as_slice()) {
+        (Some(Command::Timeline { program_id }), _) => {
+            lookup_timeline(&options, cli.output, &program_id)
+        }
+        (Some(Command::Cache { action }), _) => match action {
+            CacheCommand::Stats => cache_stats(&options),
+            CacheCommand::Prune { older_than } => prune_cache(&options, older_than),
+            CacheCommand::Clear => clear_cache(&options),
+        },
+        (None, program_ids) if is_batch => read_program_ids(program_ids, cli.input.as_deref())
+            .and_then(|program_ids| {
+                if cli.last_deployed {
+                    lookup_last_deployment_batch(&options, cli.output, cli.jobs, &program_ids)
+                } else {
+                    lookup_provenance_batch(&options, cli.output, cli.jobs, &program_ids)
+                }
+            }),
+        (None, [program_id]) if cli.last_deployed => {
+            lookup_last_deployment(&options, cli.output, program_id)
+        }
+        (None, [program_id]) => lookup_provenance(&options, cli.output, program_id),
+        (None, _) => unreachable!("clap requires a program ID when no subcommand is given"),
+    };
+
+    let output = result.unwrap_or_else(|err| {
+        if let Some(SolceptionError::Interrupted {
+            partial_result: Some(partial_result),
+            ..
+        }) = err.downcast_ref()
+        {
+            println!("{}", partial_result);
+        }
+        eprintln!("Error: {}", err);
+        std::process::exit(
+            err.downcast_ref::<SolceptionError>()
+                .map_or(1, SolceptionError::exit_code),
+        );
+    });
+
+    println!("{}", output);
 
     std::process::exit(0);
 }
+
+/// Gathers the program IDs of a batch from the arguments, with `-` standing
+/// for those read from stdin, followed by those in the input file.  Each
+/// program is looked up once, in the order first given.
+fn read_program_ids(arguments: &[String], input: Option<&str>) -> Result<Vec<String>> {
+    let mut program_ids = Vec::new();
+    for argument in arguments {
+        match argument.as_str() {
+            "-" => program_ids.extend(parse_program_ids(io::stdin().lock())?),
+            program_id => program_ids.push(program_id.to_string()),
+        }
+    }
+    match input {
+        Some("-") => program_ids.extend(parse_program_ids(io::stdin().lock())?),
+        Some(path) => {
+            let file = File::open(path).map_err(|e| {
+                anyhow!(
+                    "Failed to open the input file at {path} .  Check that the file exists and is \
+                     readable.  {e}"
+                )
+            })?;
+            program_ids.extend(parse_program_ids(BufReader::new(file))?);
+        }
+        None => {}
+    }
+
+    let mut seen = HashSet::new();
+    program_ids.retain(|program_id| seen.insert(program_id.clone()));
+    if program_ids.is_empty() {
+        return Err(anyhow!("The batch holds no program IDs to look up."));
+    }
+
+    Ok(program_ids)
+}
+
+fn parse_program_ids(reader: impl BufRead) -> Result<Vec<String>> {
+    let mut program_ids = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            program_ids.push(line.to_string());
+        }
+    }
+
+    Ok(program_ids)
+}
@@ -2,4 +2,4 @@ pub mod program_data_provenance;
 pub mod solana_queries;
 
 pub(crate) use program_data_provenance::ProgramDataProvenance;
-pub(crate) use solana_queries::SolanaQueries;
+pub use solana_queries::SolanaQueries;
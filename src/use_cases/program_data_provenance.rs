@@ -1,23 +1,94 @@
 use anyhow::Result;
-use chrono::prelude::*;
 use tracing::{debug, instrument};
 
-use crate::use_cases::SolanaQueries;
+use crate::{
+    entities::{DeploymentTimeline, LastDeployment, ProgramId, ProvenanceReport, SolceptionError},
+    use_cases::SolanaQueries,
+};
 
 pub(crate) struct ProgramDataProvenance {
     solana: Box<dyn SolanaQueries>,
+
+    /// Refuse to return a result searched from a history that is missing
+    /// the details of any of its transactions, or could not decode them.
+    strict: bool,
 }
 
 impl ProgramDataProvenance {
     #[instrument(skip(solana))]
-    pub fn new(solana: Box<dyn SolanaQueries>) -> Self {
-        Self { solana }
+    pub fn new(solana: Box<dyn SolanaQueries>, strict: bool) -> Self {
+        Self { solana, strict }
     }
 
     #[instrument(skip(self))]
-    pub fn lookup_provenance(&self, program_id: &str) -> Result<DateTime<Utc>> {
+    pub fn lookup_provenance(&self, program_id: &ProgramId) -> Result<ProvenanceReport> {
         debug!("Beginning program provenance via gateway lookup for {program_id}.");
 
-        self.solana.get_first_deployed_slot_timestamp(program_id)
+        let report = self.solana.get_provenance_report(program_id)?;
+        self.enforce_strict(
+            program_id,
+            &report.unrecoverable_signatures,
+            &report.undecoded_signatures,
+        )?;
+
+        Ok(report)
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment> {
+        debug!("Beginning last deployment via gateway lookup for {program_id}.");
+
+        self.solana.get_last_deployment(program_id)
+    }
+
+    #[instrument(skip(self))]
+    pub fn lookup_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline> {
+        debug!("Beginning deployment timeline via gateway lookup for {program_id}.");
+
+        let timeline = self.solana.get_deployment_timeline(program_id)?;
+        if timeline.interrupted_after.is_none() {
+            self.enforce_strict(
+                program_id,
+                &timeline.unrecoverable_signatures,
+                &timeline.undecoded_signatures,
+            )?;
+        }
+
+        Ok(timeline)
+    }
+
+    fn enforce_strict(
+        &self,
+        program_id: &ProgramId,
+        unrecoverable_signatures: &[String],
+        undecoded_signatures: &[String],
+    ) -> Result<(), SolceptionError> {
+        if !self.strict {
+            return Ok(());
+        }
+
+        if !unrecoverable_signatures.is_empty() {
+            return Err(SolceptionError::HistoryTruncated {
+                program_id: program_id.to_string(),
+                reason: format!(
+                    "{} transaction details could not be retrieved, and strict mode refuses a \
+                     result that could be missing a deployment",
+                    unrecoverable_signatures.len()
+                ),
+            });
+        }
+        if !undecoded_signatures.is_empty() {
+            return Err(SolceptionError::DecodeFailure {
+                what: format!(
+                    "{} transactions for program_id: {program_id}",
+                    undecoded_signatures.len()
+                ),
+                reason: "strict mode refuses a result that could be missing a deployment they \
+                         carry"
+                    .to_string(),
+            });
+        }
+
+        Ok(())
     }
 }
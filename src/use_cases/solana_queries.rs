@@ -1,6 +1,19 @@
 use anyhow::Result;
-use chrono::prelude::*;
 
-pub(crate) trait SolanaQueries {
-    fn get_first_deployed_slot_timestamp(&self, program_id: &str) -> Result<DateTime<Utc>>;
+use crate::entities::{DeploymentTimeline, LastDeployment, ProgramId, ProvenanceReport};
+
+/// The ledger queries the provenance use case depends on.  The RPC gateway
+/// is the built-in implementation; embedding programs can supply their own,
+/// e.g. backed by an indexer, with
+/// [`SolceptionBuilder::gateway`](crate::SolceptionBuilder::gateway).
+///
+/// Failures should use a [`crate::SolceptionError`] variant where one fits,
+/// so callers and `solc` exit codes can tell the causes apart.  Batch
+/// lookups share one implementation across threads.
+pub trait SolanaQueries: Send + Sync {
+    fn get_provenance_report(&self, program_id: &ProgramId) -> Result<ProvenanceReport>;
+
+    fn get_deployment_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline>;
+
+    fn get_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment>;
 }
@@ -3,6 +3,68 @@ use predicates::prelude::*;
 const MARINADE_STAKING_PROGRAM_ID: &str = "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD";
 const RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS: &str =
     "HP3G4ptUEd6C4urhjM5sV57RgoarNCPNrWu7vWrCkYWg";
+const FEW_DEPLOYMENTS_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/few_deployments_history.ndjson"
+);
+const FEW_DEPLOYMENTS_PROGRAMDATA_ADDRESS: &str = "HgPFpRZj2m6CkL1SJVkofEN34QW2qH7wxutehJk7Wzki";
+const FEW_DEPLOYMENTS_NEWEST_SIGNATURE: &str =
+    "3M9sCXpA5brCepnTgsCPuHjcXxJTjHzofFtaSnSptvKSCxoNqCzCmphH3YxfcbwnNKPN57ggY7WVUSQje9K3RB7M";
+const FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE: &str =
+    "5gB4csMmDViFS5e86wdChmC19XiFoQFmeaLeD9Y9rEfdRtMKe4E3QUHVnTtsMu4xGE5GnXnRohXqzbYce9dy1oBq";
+const NEVER_UPGRADED_PROGRAM_ID: &str = "GfC9zSCG1MYM6Nf8vdytDrc3hNNBb2jMLnYJkGnPxjnQ";
+const NEVER_UPGRADED_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/single_deployment_history.ndjson"
+);
+const MARINADE_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/marinade_history.ndjson"
+);
+const EMPTY_HISTORY_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/empty_history.ndjson"
+);
+const TWO_PAGE_PROGRAM_ID: &str = "45LjhW3veUCLWtFXkUpMRCaLhtV2heLRCuikAvQRWU5a";
+const TWO_PAGE_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/two_page_history.ndjson"
+);
+/// The newest transaction of the older page, which precedes the deploy.
+const TWO_PAGE_OLDER_PAGE_NEWEST_SIGNATURE: &str =
+    "3YW6e135j6NhmmKVaXULhsSatvuKJtYBrQdLr5QtNfbxxkUUYLwN3e6co7J79sU37f6DKxbkxqAJWAnpDxw994kj";
+const LEGACY_LOADER_PROGRAM_ID: &str = "CfpQu8wzU1grnv2EqX7VEWVvAGhKGx9dsDVMu2A7ytu6";
+const LEGACY_LOADER_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/legacy_loader_history.ndjson"
+);
+const LOADER_V4_PROGRAM_ID: &str = "3pmuWsGcoWVJKe2LvHuXsPv5Dz5wqVVoJ9JtVxfu7mAo";
+const LOADER_V4_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/loader_v4_history.ndjson"
+);
+const FAILED_DEPLOY_PROGRAM_ID: &str = "9BkTxQGzbRRsyn1b5FDniADtHvFr66y9NfL9CFcS4rSp";
+const FAILED_DEPLOY_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/failed_deploy_history.ndjson"
+);
+const FAILED_DEPLOY_ATTEMPT_SIGNATURE: &str =
+    "3Ugu1xi8f2SfP5g53EMWAnGKmfEgfC4oSbfR4tkN8XjqVCdjTmaFjyL3ZW4o2AY8v3rYSjkeRdp3gbcZVmvrA96C";
+const UNDECODABLE_UPGRADE_PROGRAM_ID: &str = "HgiFvwS4FkAyM9cB3Yw8cRj9BkpjFPYjHvYQ6kJFoRPy";
+const UNDECODABLE_UPGRADE_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/undecodable_upgrade_history.ndjson"
+);
+const UNDECODABLE_UPGRADE_SIGNATURE: &str =
+    "XcenhWgXwKGeQG4Nmr13bXqjayXwMeCK1Myntc63atkg5e8YfJky5o15VkbrYjUvG6W6jbsS2K4Yw66EkUWAkce";
+const NON_PROGRAM_ACCOUNTS_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/non_program_accounts.ndjson"
+);
+const WALLET_ADDRESS: &str = "GfsJWjmGXMfct8JMR9Lm9ySUnniZbnGUTQDbT8ipWf9U";
+const TOKEN_MINT_ADDRESS: &str = "FqUwnBMN1shpeqKVm7W5fN73tvrjVr19TQFFgkoFFzhq";
+const PROGRAMDATA_ADDRESS: &str = "BBMg6EMtzKYT4ieKWvbdjoaQVtVGY3XHzT6H15fa1daj";
+const PROGRAM_OF_PROGRAMDATA_ADDRESS: &str = "5HW2Stifh7oiGWUfqK1DPcLSJ2UmvDQc7hdrfdCgD5U4";
 
 fn test_command() -> assert_cmd::Command {
     assert_cmd::Command::cargo_bin("solc").expect(
@@ -51,7 +113,8 @@ fn invoking_with_help_flag_prints_help() {
         .success()
         .stdout(predicate::str::contains("Usage:"))
         .stdout(predicate::str::contains("Arguments:"))
-        .stdout(predicate::str::contains("Options:"));
+        .stdout(predicate::str::contains("Options:"))
+        .stdout(predicate::str::contains("[text, json, ndjson, yaml, csv]"));
 }
 
 #[test]
@@ -67,120 +130,235 @@ fn invoking_with_version_flag_prints_version() {
         .stdout(predicate::str::contains("solception"));
 }
 
-/*
- * This test currently takes ~7 hours to complete against the devnet public
- * RPC node.
- *
- * Against a private full-history node, this test take ~13 minutes to
- * complete when no other tests are running.  Performance as part of the full
- * integ suite depends on the node's load at the time of the test run, but
- * averages around 30 minutes.
- *
- * This duration is because the blocking RpcClient respects the HTTP 429
- * headers from the node, resulting in optimal performance within the
- * constraints of the node's rate limiting due to the headers communicating
- * the actual delay before the next token refresh on the three applicable
- * throttle limits effecting the client.
- */
 #[test]
 fn invoking_with_verbose_flag_once_prints_warn_level_logs() {
     test_command()
+        .arg("--replay")
+        .arg(TWO_PAGE_FIXTURE)
         .arg("--verbose")
-        .arg(MARINADE_STAKING_PROGRAM_ID)
+        .arg(TWO_PAGE_PROGRAM_ID)
         .assert()
         .append_context(
             "verbosity",
-            "Invoking with the `--verbose` flag once should print WARN log events.",
+            "Invoking with the `--verbose` flag once should print WARN log events to stderr, \
+             leaving only the result on stdout.",
         )
         .success()
+        .stdout(predicate::str::starts_with("2023-11-14T22:13:40+00:00\n"))
+        .stdout(predicate::str::contains("WARN").not())
         .stderr(predicate::str::contains("WARN"))
         .stderr(predicate::str::contains(
-            "solception::adapters::gateways::solana::interface",
+            "solception::adapters::gateways::solana::pipeline",
         ))
+        .stderr(predicate::str::contains("The number of transactions for"))
+        .stderr(predicate::str::contains(
+            " exceeds 1000.  This may take a long time to retrieve all transaction details, \
+             depending on the chosen cluster RPC node's rate limits!",
+        ))
+        .stderr(predicate::str::contains("INFO").not());
+}
+
+#[test]
+fn invoking_with_processed_commitment_warns_and_uses_confirmed() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--verbose")
+        .arg("--commitment")
+        .arg("processed")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "commitment",
+            "Invoking with `processed` commitment should warn that the RPC node does not serve \
+             history at it, and look up at `confirmed` commitment instead.",
+        )
+        .success()
+        .stdout(predicate::str::diff("2024-08-03T17:11:30+00:00\n"))
         .stderr(predicate::str::contains(
-            "The number of transactions for program_id:",
+            "solception::adapters::gateways::config::interface",
         ))
         .stderr(predicate::str::contains(
-            " exceeds 1000.  This may take a long time to retrieve all transaction details, \
-             depending on the chosen cluster RPC node\'s rate limits!",
+            "The RPC node does not serve transaction history at `processed` commitment.",
+        ));
+}
+
+#[test]
+fn invoking_with_confirmed_commitment_warns_that_the_cache_is_bypassed() {
+    test_command()
+        .arg("--verbose")
+        .arg("--commitment")
+        .arg("confirmed")
+        .arg("not-a-pubkey")
+        .assert()
+        .append_context(
+            "commitment",
+            "Invoking with `confirmed` commitment should warn that the cache is not used, since \
+             only finalized data is cached.",
+        )
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Not using the cache, as only finalized data is cached",
         ));
 }
 
 #[test]
 fn invoking_with_verbose_flag_twice_prints_info_level_logs() {
     test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
         .arg("-vv")
         .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
         .assert()
         .append_context(
             "verbosity",
-            "Invoking with the `--verbose` flag twice should print INFO log events.",
+            "Invoking with the `--verbose` flag twice should print INFO log events to stderr, \
+             leaving only the result on stdout.",
         )
         .success()
-        .stdout(predicate::str::contains("INFO"))
-        .stdout(predicate::str::contains(
+        .stdout(predicate::str::diff("2024-08-03T17:11:30+00:00\n"))
+        .stderr(predicate::str::contains("INFO"))
+        .stderr(predicate::str::contains(
             "This may take some time, depending on the number of transactions and the chosen \
              cluster RPC node's rate limits.",
-        ));
+        ))
+        .stderr(predicate::str::contains("DEBUG").not());
 }
 
 #[test]
 fn invoking_with_verbose_flag_thrice_prints_debug_level_logs() {
     test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
         .arg("-vvv")
         .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
         .assert()
         .append_context(
             "verbosity",
-            "Invoking with the `--verbose` flag thrice should print DEBUG log events.",
+            "Invoking with the `--verbose` flag thrice should print DEBUG log events to stderr, \
+             leaving only the result on stdout.",
         )
         .success()
-        .stdout(predicate::str::contains("INFO"))
-        .stdout(predicate::str::contains(
+        .stdout(predicate::str::diff("2024-08-03T17:11:30+00:00\n"))
+        .stderr(predicate::str::contains("INFO"))
+        .stderr(predicate::str::contains(
             "This may take some time, depending on the number of transactions and the chosen \
              cluster RPC node's rate limits.",
         ))
-        .stdout(predicate::str::contains("DEBUG"))
-        .stdout(predicate::str::contains("reqwest::connect"))
-        .stdout(predicate::str::contains("starting new connection:"))
-        .stdout(predicate::str::contains("hyper::client::connect::dns"))
-        .stdout(predicate::str::contains("resolving host="))
-        .stdout(predicate::str::contains("rustls::client::hs"))
-        .stdout(predicate::str::contains("No cached session for DnsName"));
+        .stderr(predicate::str::contains("DEBUG"))
+        .stderr(predicate::str::contains(
+            "solception::adapters::gateways::solana::pipeline",
+        ))
+        .stderr(predicate::str::contains("Crawled 3 transaction summaries"))
+        .stderr(predicate::str::contains("TRACE").not());
 }
 
 #[test]
 fn invoking_with_verbose_flag_four_times_prints_trace_level_logs() {
     test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
         .arg("-vvvv")
         .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
         .assert()
         .append_context(
             "verbosity",
-            "Invoking with the `--verbose` flag four times should print TRACE log events.",
+            "Invoking with the `--verbose` flag four times should print TRACE log events to \
+             stderr, leaving only the result on stdout.",
         )
         .success()
-        .stdout(predicate::str::contains("INFO"))
-        .stdout(predicate::str::contains(
+        .stdout(predicate::str::diff("2024-08-03T17:11:30+00:00\n"))
+        .stderr(predicate::str::contains("INFO"))
+        .stderr(predicate::str::contains(
             "This may take some time, depending on the number of transactions and the chosen \
              cluster RPC node's rate limits.",
         ))
-        .stdout(predicate::str::contains("TRACE"))
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains("TRACE"))
+        .stderr(predicate::str::contains(
             "solception::adapters::gateways::telemetry::interface",
         ))
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains(
             "Initializing tracing with debug level: TRACE",
         ))
-        .stdout(predicate::str::contains("hyper::client::pool"))
-        .stdout(predicate::str::contains(
-            "checkout waiting for idle connection:",
+        .stderr(predicate::str::contains(
+            "solception::adapters::gateways::solana::replay",
+        ))
+        .stderr(predicate::str::contains(
+            "Replaying response 0 for getSignaturesForAddress",
         ))
-        .stdout(predicate::str::contains("DEBUG"))
-        .stdout(predicate::str::contains("reqwest::connect"))
-        .stdout(predicate::str::contains("starting new connection:"))
-        .stdout(predicate::str::contains("hyper::client::connect::http"))
-        .stdout(predicate::str::contains("Http::connect; scheme="));
+        .stderr(predicate::str::contains("DEBUG"))
+        .stderr(predicate::str::contains(
+            "solception::adapters::gateways::solana::pipeline",
+        ))
+        .stderr(predicate::str::contains("Crawled 3 transaction summaries"));
+}
+
+#[test]
+fn invoking_with_log_file_appends_log_events_to_it_instead_of_stderr() {
+    let log_dir = tempfile::tempdir().unwrap();
+    let log_file = log_dir.path().join("solc.log");
+
+    test_command()
+        .arg("-vvvv")
+        .arg("--log-file")
+        .arg(&log_file)
+        .arg("--replay")
+        .arg(EMPTY_HISTORY_FIXTURE)
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "log-file",
+            "Invoking with a log file should write log events there, leaving stdout empty and \
+             stderr with only the error.",
+        )
+        .failure()
+        .code(5)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("TRACE").not());
+
+    let log = std::fs::read_to_string(&log_file).expect("The log file should have been created.");
+    assert!(log.contains("Initializing tracing with debug level: TRACE"));
+}
+
+#[test]
+fn invoking_with_json_log_format_and_a_log_filter_prints_only_matching_json_events() {
+    test_command()
+        .arg("--log-format")
+        .arg("json")
+        .arg("--log-filter")
+        .arg("solception=debug")
+        .arg("--replay")
+        .arg(EMPTY_HISTORY_FIXTURE)
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "log-format",
+            "Invoking with JSON logs filtered to solception debug events should print one JSON \
+             object per event, from solception only.",
+        )
+        .failure()
+        .code(5)
+        .stderr(predicate::str::contains(r#"{"timestamp":"#))
+        .stderr(predicate::str::contains(r#""level":"DEBUG""#))
+        .stderr(predicate::str::contains(r#""target":"solception::"#))
+        .stderr(predicate::str::contains(r#""level":"TRACE""#).not());
+}
+
+#[test]
+fn invoking_with_an_invalid_log_filter_prints_error() {
+    test_command()
+        .arg("--log-filter")
+        .arg("solception=loudest")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "log-filter",
+            "Invoking with an unparseable log filter should print an error message.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("Failed to parse the log filter"));
 }
 
 #[test]
@@ -201,6 +379,8 @@ fn invoking_with_invalid_argument_prints_error() {
 #[test]
 fn invoking_with_valid_program_id_succeeds() {
     test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
         .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
         .assert()
         .append_context(
@@ -211,24 +391,618 @@ fn invoking_with_valid_program_id_succeeds() {
         .stdout(predicate::str::contains("2024-08-03T17:11:30+00:00"));
 }
 
+#[test]
+fn invoking_with_json_output_prints_a_versioned_inception_record() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--output")
+        .arg("json")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "output",
+            "Invoking with JSON output should print the inception as a versioned record.",
+        )
+        .success()
+        .stdout(predicate::str::contains(r#""schema_version": 1"#))
+        .stdout(predicate::str::contains(r#""record_type": "inception""#))
+        .stdout(predicate::str::contains(format!(
+            r#""program_id": "{RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS}""#
+        )))
+        .stdout(predicate::str::contains(format!(
+            r#""rpc_url": "replay:{FEW_DEPLOYMENTS_FIXTURE}""#
+        )))
+        .stdout(predicate::str::contains(
+            r#""block_time": "2024-08-03T17:11:30+00:00""#,
+        ))
+        .stdout(predicate::str::contains(r#""block_time_unix": 1722705090"#))
+        .stdout(predicate::str::contains(r#""complete": true"#));
+}
+
+#[test]
+fn invoking_with_an_unknown_output_format_prints_error() {
+    test_command()
+        .arg("--output")
+        .arg("xml")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "output",
+            "Invoking with an unknown output format should print a usage error.",
+        )
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("Unknown output format: xml"));
+}
+
+#[test]
+fn invoking_with_a_cluster_moniker_resolves_the_rpc_url() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("-vvv")
+        .arg("--url")
+        .arg("d")
+        .arg("--timeout")
+        .arg("60")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "cluster-url",
+            "Invoking with a cluster moniker and timeout should target that cluster's public RPC \
+             node.",
+        )
+        .success()
+        .stdout(predicate::str::contains("2024-08-03T17:11:30+00:00"))
+        .stderr(predicate::str::contains(
+            "Resolved RPC node URL: https://api.devnet.solana.com",
+        ))
+        .stderr(predicate::str::contains("timeout: Some(60s)"));
+}
+
+#[test]
+fn invoking_with_rpc_url_environment_variable_resolves_the_rpc_url() {
+    test_command()
+        .env("SOLC_RPC_URL", "devnet")
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("-vvv")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "environment",
+            "Invoking with `SOLC_RPC_URL` set should target that cluster's RPC node.",
+        )
+        .success()
+        .stdout(predicate::str::contains("2024-08-03T17:11:30+00:00"))
+        .stderr(predicate::str::contains(
+            "Resolved RPC node URL: https://api.devnet.solana.com",
+        ));
+}
+
+#[test]
+fn invoking_with_a_missing_config_file_prints_error() {
+    test_command()
+        .arg("--config")
+        .arg("/nonexistent/solc/config.yml")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "config-file",
+            "Invoking with an explicit config file that does not exist should fail before any RPC \
+             calls are made.",
+        )
+        .failure()
+        .stderr(predicate::str::contains(
+            "Failed to load the solc config file at /nonexistent/solc/config.yml",
+        ));
+}
+
+#[test]
+fn invoking_with_a_zero_request_rate_prints_error() {
+    test_command()
+        .arg("--rps")
+        .arg("0")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "zero-rps",
+            "Invoking with a request budget of zero should fail before any RPC calls are made.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("must both be at least 1"));
+}
+
+#[test]
+fn invoking_with_an_invalid_program_id_exits_with_invalid_pubkey_code() {
+    test_command()
+        .arg("not-a-base58-pubkey")
+        .assert()
+        .append_context(
+            "invalid-pubkey",
+            "Invoking with a program ID that is not a base 58 public key should fail with the \
+             documented exit code before any RPC calls are made.",
+        )
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains("Failed to parse program_id"));
+}
+
+#[test]
+fn invoking_with_strict_flag_succeeds_when_the_history_is_complete() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--strict")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "strict",
+            "Invoking with the `--strict` flag should still print the inception timestamp when \
+             every transaction detail in the history was retrieved.",
+        )
+        .success()
+        .stdout(predicate::str::contains("2024-08-03T17:11:30+00:00"))
+        .stdout(predicate::str::contains("Unrecoverable transactions").not());
+}
+
+#[test]
+fn invoking_timeline_with_an_undecodable_transaction_marks_the_record_incomplete() {
+    let output = test_command()
+        .arg("--replay")
+        .arg(UNDECODABLE_UPGRADE_FIXTURE)
+        .arg("--output")
+        .arg("json")
+        .arg("timeline")
+        .arg(UNDECODABLE_UPGRADE_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "undecoded",
+            "Invoking the timeline on a history holding a transaction that cannot be decoded \
+             should list its signature and not claim the record is complete.",
+        )
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let record: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(record["complete"], false);
+    assert_eq!(
+        record["undecoded_signatures"],
+        serde_json::json!([UNDECODABLE_UPGRADE_SIGNATURE])
+    );
+    assert_eq!(record["deployments"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn invoking_with_strict_flag_refuses_a_timeline_with_an_undecodable_transaction() {
+    test_command()
+        .arg("--replay")
+        .arg(UNDECODABLE_UPGRADE_FIXTURE)
+        .arg("--strict")
+        .arg("timeline")
+        .arg(UNDECODABLE_UPGRADE_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "strict",
+            "Invoking with the `--strict` flag should refuse a timeline when a transaction in the \
+             history could not be decoded, with the decode failure exit code.",
+        )
+        .failure()
+        .code(9)
+        .stderr(predicate::str::contains("strict mode refuses"));
+}
+
+#[test]
+fn invoking_cache_stats_on_an_empty_cache_reports_it_is_empty() {
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    test_command()
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg("cache")
+        .arg("stats")
+        .assert()
+        .append_context(
+            "cache-stats",
+            "Invoking `cache stats` on a cache that was never written should succeed without any \
+             RPC calls.",
+        )
+        .success()
+        .stdout(predicate::str::contains("is empty."));
+}
+
+#[test]
+fn invoking_offline_without_a_populated_cache_prints_error() {
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    test_command()
+        .arg("--offline")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "offline",
+            "Invoking with the `--offline` flag before anything was cached should fail without \
+             any RPC calls.",
+        )
+        .failure()
+        .code(10)
+        .stderr(predicate::str::contains(
+            "Run the lookup once without `--offline` to populate the cache.",
+        ));
+}
+
+#[test]
+fn invoking_with_resume_flag_without_a_checkpoint_starts_from_the_beginning() {
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--resume")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "resume",
+            "Invoking with the `--resume` flag when no checkpoint was saved should run the full \
+             lookup instead.",
+        )
+        .success()
+        .stdout(predicate::str::contains("2024-08-03T17:11:30+00:00"));
+}
+
+#[test]
+fn invoking_with_resume_flag_refuses_a_checkpoint_missing_from_the_history() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let checkpoints_dir = cache_dir.path().join("checkpoints");
+    std::fs::create_dir_all(&checkpoints_dir).unwrap();
+    std::fs::write(
+        checkpoints_dir.join(format!("inception-{LEGACY_LOADER_PROGRAM_ID}.json")),
+        serde_json::json!({
+            "rpc_url": format!("replay:{LEGACY_LOADER_FIXTURE}"),
+            "programdata_address": LEGACY_LOADER_PROGRAM_ID,
+            "scan": {
+                "searched": 1,
+                "unrecoverable_signatures": [],
+                "undecoded_signatures": [],
+                "last_signature": "1111111111111111111111111111111111111111111111111111111111111111",
+            },
+            "reducer": { "inception": null, "failed_attempts": [] },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    test_command()
+        .arg("--replay")
+        .arg(LEGACY_LOADER_FIXTURE)
+        .arg("--resume")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg(LEGACY_LOADER_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "resume",
+            "Invoking with the `--resume` flag when the checkpoint's last transaction is not in \
+             the history should fail, rather than skip the whole history and report that no \
+             deployment was found.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("is no longer in the history"));
+}
+
+#[test]
+fn invoking_with_replay_flag_does_not_retry_a_request_missing_from_the_fixture() {
+    let fixture = tempfile::NamedTempFile::new().unwrap();
+    let lines: Vec<&str> = include_str!("fixtures/few_deployments_history.ndjson")
+        .lines()
+        .filter(|line| {
+            !(line.contains("\"getTransaction\"")
+                && line.contains(FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE))
+        })
+        .collect();
+    std::fs::write(fixture.path(), lines.join("\n")).unwrap();
+
+    test_command()
+        .arg("--replay")
+        .arg(fixture.path())
+        .arg("-vvv")
+        .arg("timeline")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "replay",
+            "Invoking with the `--replay` flag when a transaction is missing from the fixture \
+             should give up on it at once, rather than retry a request that can never succeed.",
+        )
+        .success()
+        .stdout(predicate::str::contains("Unrecoverable transactions (1)"))
+        .stdout(predicate::str::contains(
+            FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE,
+        ))
+        .stderr(predicate::str::contains("has no recorded response"))
+        .stderr(predicate::str::contains("retrying in").not());
+}
+
+#[test]
+fn invoking_with_a_replayed_empty_history_exits_with_no_deployments_code() {
+    test_command()
+        .arg("--replay")
+        .arg(EMPTY_HISTORY_FIXTURE)
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "replay",
+            "Invoking with a history that holds no transactions should report that no deployment \
+             was found, without any network access.",
+        )
+        .failure()
+        .code(5)
+        .stderr(predicate::str::contains("No deployment found"));
+}
+
+#[test]
+fn invoking_with_a_replayed_legacy_loader_program_finds_its_finalize() {
+    test_command()
+        .arg("--replay")
+        .arg(LEGACY_LOADER_FIXTURE)
+        .arg(LEGACY_LOADER_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "loaders",
+            "Invoking on a program owned by BPFLoader2111 should report the transaction that \
+             finalized it as its inception.",
+        )
+        .success()
+        .stdout(predicate::str::diff("2023-11-14T22:15:01+00:00\n"));
+}
+
+#[test]
+fn invoking_timeline_on_a_replayed_loader_v4_program_lists_its_migration_and_redeploys() {
+    test_command()
+        .arg("--replay")
+        .arg(LOADER_V4_FIXTURE)
+        .arg("--output")
+        .arg("csv")
+        .arg("timeline")
+        .arg(LOADER_V4_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "loaders",
+            "Invoking the timeline on a program migrated from BPFLoaderUpgradeab1e to LoaderV4 \
+             should list its deploys from both loaders, with every deploy after the first as an \
+             upgrade, and the LoaderV4 instructions the migration invoked folded into its row.",
+        )
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                "(?s),deploy,200,.*,migrate,250,.*,upgrade,302,.*,retract,400,.*,upgrade,401,.*,\
+                 set-authority,500,.*,deploy,600,.*,false,",
+            )
+            .unwrap()
+            .and(predicate::str::contains(",250,").count(1)),
+        );
+}
+
+#[test]
+fn invoking_with_a_wallet_address_exits_with_not_a_program_code() {
+    test_command()
+        .arg("--replay")
+        .arg(NON_PROGRAM_ACCOUNTS_FIXTURE)
+        .arg(WALLET_ADDRESS)
+        .assert()
+        .append_context(
+            "preflight",
+            "Invoking on a wallet should fail before searching any history, saying it is a wallet.",
+        )
+        .failure()
+        .code(4)
+        .stderr(predicate::str::contains("it is a wallet"));
+}
+
+#[test]
+fn invoking_timeline_with_a_token_mint_address_exits_with_not_a_program_code() {
+    test_command()
+        .arg("--replay")
+        .arg(NON_PROGRAM_ACCOUNTS_FIXTURE)
+        .arg("timeline")
+        .arg(TOKEN_MINT_ADDRESS)
+        .assert()
+        .append_context(
+            "preflight",
+            "Invoking the timeline on a token mint should fail before searching any history, \
+             saying it is a token mint.",
+        )
+        .failure()
+        .code(4)
+        .stderr(predicate::str::contains("it is a token mint"));
+}
+
+#[test]
+fn invoking_with_a_programdata_address_names_the_program_it_belongs_to() {
+    test_command()
+        .arg("--replay")
+        .arg(NON_PROGRAM_ACCOUNTS_FIXTURE)
+        .arg(PROGRAMDATA_ADDRESS)
+        .assert()
+        .append_context(
+            "preflight",
+            "Invoking on a ProgramData account should fail before searching any history, naming \
+             the program to look up instead.",
+        )
+        .failure()
+        .code(4)
+        .stderr(predicate::str::contains(format!(
+            "it is the ProgramData account of program {PROGRAM_OF_PROGRAMDATA_ADDRESS}"
+        )));
+}
+
+#[test]
+fn invoking_with_a_missing_replay_file_prints_error() {
+    test_command()
+        .arg("--replay")
+        .arg("/nonexistent/solc/fixture.ndjson")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "replay",
+            "Invoking with a replay file that does not exist should fail before any lookup.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("Failed to open the replay file"));
+}
+
+#[test]
+fn invoking_with_record_and_resume_flags_prints_error() {
+    let record_dir = tempfile::tempdir().unwrap();
+
+    test_command()
+        .arg("--record")
+        .arg(record_dir.path().join("recording.ndjson"))
+        .arg("--resume")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "record",
+            "Invoking with a recording and `--resume` should fail before any lookup, since a \
+             resumed lookup would leave requests out of the recording.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("cannot resume a checkpoint"));
+}
+
+#[test]
+fn invoking_with_several_program_ids_prints_one_csv_row_per_program() {
+    test_command()
+        .arg("--replay")
+        .arg(EMPTY_HISTORY_FIXTURE)
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .arg("not-a-program-id")
+        .assert()
+        .append_context(
+            "batch",
+            "Invoking with several program IDs should print a CSV header and one row per program, \
+             with failed lookups as error rows rather than failing the batch.",
+        )
+        .success()
+        .stdout(predicate::str::starts_with(
+            "schema_version,record_type,program_id,",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "1,error,{RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS},"
+        )))
+        .stdout(predicate::str::contains("1,error,not-a-program-id,"))
+        .stdout(predicate::str::is_match(r",5\n1,error,not-a-program-id,.*,3\n$").unwrap());
+}
+
+#[test]
+fn invoking_with_input_from_stdin_prints_one_ndjson_row_per_program() {
+    test_command()
+        .arg("--replay")
+        .arg(EMPTY_HISTORY_FIXTURE)
+        .arg("--output")
+        .arg("ndjson")
+        .arg("--input")
+        .arg("-")
+        .write_stdin(
+            [
+                "# audited programs",
+                RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS,
+                "",
+                RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS,
+            ]
+            .join("\n"),
+        )
+        .assert()
+        .append_context(
+            "batch",
+            "Invoking with program IDs on stdin should skip comments, blank lines, and \
+             duplicates, and print one NDJSON row per program.",
+        )
+        .success()
+        .stdout(predicate::str::contains(r#""record_type":"error""#).count(1))
+        .stdout(predicate::str::contains(r#""exit_code":5"#));
+}
+
+#[test]
+fn invoking_with_last_deployed_flag_prints_last_deployment_timestamp() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--last-deployed")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "last-deployed",
+            "Invoking with the `--last-deployed` flag should print the RFC 3339 timestamp of the \
+             slot recorded in the ProgramData account.",
+        )
+        .success()
+        .stdout(predicate::str::diff("2024-08-03T17:30:21+00:00\n"));
+}
+
+#[test]
+fn invoking_with_last_deployed_flag_on_a_never_upgraded_program_notes_it_on_a_second_line() {
+    test_command()
+        .arg("--replay")
+        .arg(NEVER_UPGRADED_FIXTURE)
+        .arg("--last-deployed")
+        .arg(NEVER_UPGRADED_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "last-deployed",
+            "Invoking with the `--last-deployed` flag on a program that was never upgraded should \
+             print the timestamp, then note that it is also the inception.",
+        )
+        .success()
+        .stdout(predicate::str::diff(
+            "2024-01-01T00:00:00+00:00\nNever upgraded: this deployment is also the program's \
+             inception.\n",
+        ));
+}
+
+#[test]
+fn invoking_timeline_subcommand_prints_every_deployment() {
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("timeline")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "timeline",
+            "Invoking the `timeline` subcommand should print a row for each deployment, starting \
+             with the initial deploy.",
+        )
+        .success()
+        .stdout(predicate::str::contains("SIGNATURE"))
+        .stdout(predicate::str::contains("FEE PAYER"))
+        .stdout(predicate::str::contains(
+            "2024-08-03T17:11:30+00:00  deploy",
+        ));
+}
+
 /*
- * This test currently takes ~7 hours to complete against the devnet public
- * RPC node.
- *
- * Against a private full-history node, this test take ~13 minutes to
- * complete when no other tests are running.  Performance as part of the full
- * integ suite depends on the node's load at the time of the test run, but
- * averages around 30 minutes.
- *
- * This duration is because the blocking RpcClient respects the HTTP 429
- * headers from the node, resulting in optimal performance within the
- * constraints of the node's rate limiting due to the headers communicating
- * the actual delay before the next token refresh on the three applicable
- * throttle limits effecting the client.
+ * Marinade has millions of user invocations, but only a handful of
+ * transactions against its ProgramData account.  Crawling the program ID
+ * itself took ~7 hours against the devnet public RPC node; crawling the
+ * ProgramData account completes in seconds.
  */
 #[test]
-fn invoking_with_an_extreme_history_correctly_paginates_the_full_available_history() {
+fn invoking_with_a_heavily_invoked_program_searches_only_its_programdata_history() {
     test_command()
+        .arg("--replay")
+        .arg(MARINADE_FIXTURE)
         .arg(MARINADE_STAKING_PROGRAM_ID)
         .assert()
         .append_context(
@@ -238,3 +1012,219 @@ fn invoking_with_an_extreme_history_correctly_paginates_the_full_available_histo
         .success()
         .stdout(predicate::str::contains("2022-04-24T11:02:50+00:00"));
 }
+
+#[test]
+fn invoking_with_a_failed_deploy_before_the_inception_lists_it_apart() {
+    test_command()
+        .arg("--replay")
+        .arg(FAILED_DEPLOY_FIXTURE)
+        .arg(FAILED_DEPLOY_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "failed-attempts",
+            "Invoking on a program whose first deploy failed should report the deploy that \
+             succeeded, and list the failed one with its error below the timestamp.",
+        )
+        .success()
+        .stdout(predicate::str::starts_with("2024-04-01T19:34:20+00:00\n"))
+        .stdout(predicate::str::contains("Failed deployment attempts:"))
+        .stdout(
+            predicate::str::is_match(format!(
+                "{FAILED_DEPLOY_ATTEMPT_SIGNATURE} .* custom program error: 0x0"
+            ))
+            .unwrap(),
+        );
+}
+
+#[test]
+fn invoking_with_output_flag_includes_failed_deploys_before_the_inception() {
+    test_command()
+        .arg("--replay")
+        .arg(FAILED_DEPLOY_FIXTURE)
+        .arg("--output")
+        .arg("json")
+        .arg(FAILED_DEPLOY_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "failed-attempts",
+            "Invoking with `--output json` on a program whose first deploy failed should list the \
+             failed one in `failed_attempts`.",
+        )
+        .success()
+        .stdout(predicate::str::contains(r#""slot": 280000150"#))
+        .stdout(predicate::str::is_match(format!(
+            r#"(?s)"failed_attempts": \[.*"signature": "{FAILED_DEPLOY_ATTEMPT_SIGNATURE}".*"succeeded": false"#
+        ))
+        .unwrap());
+}
+
+/*
+ * The fixture holds 1005 transactions against the ProgramData account, so
+ * the RPC node returns them in two pages, the second requested with the
+ * oldest signature of the first as its `before` cursor.  Only the older page
+ * and the oldest part of the newer one have transaction details in the
+ * fixture, so the lookup only succeeds with `--strict` when it searches
+ * oldest first.  The older page opens with four failed deploys, which are
+ * listed as failed attempts before the inception.
+ */
+#[test]
+fn invoking_with_a_history_spanning_two_pages_searches_it_oldest_first() {
+    test_command()
+        .arg("--replay")
+        .arg(TWO_PAGE_FIXTURE)
+        .arg("--strict")
+        .arg("-vvv")
+        .arg(TWO_PAGE_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "pagination",
+            "Invoking on a history longer than one page should crawl every page, then search the \
+             oldest transactions first.",
+        )
+        .success()
+        .stdout(predicate::str::starts_with("2023-11-14T22:13:40+00:00\n"))
+        .stdout(predicate::str::contains("custom program error: 0x1").count(4))
+        .stderr(predicate::str::contains(
+            "Crawled 1005 transaction summaries for 4sUBr1W9CDFpnG1aqTXJHLABFXSxjn2CrWBkXuaTVnTM \
+             across 2 pages.",
+        ))
+        .stderr(predicate::str::contains("Scanned 6 transactions"));
+}
+
+/// Writes a timeline checkpoint for the few-deployments fixture, as saved by
+/// a scan that started at `first_signature` and stopped after
+/// `last_signature` without finding any deployment.
+fn write_timeline_checkpoint(
+    cache_dir: &std::path::Path,
+    first_signature: &str,
+    last_signature: &str,
+) {
+    let checkpoints_dir = cache_dir.join("checkpoints");
+    std::fs::create_dir_all(&checkpoints_dir).unwrap();
+    std::fs::write(
+        checkpoints_dir.join(format!(
+            "timeline-{RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS}.json"
+        )),
+        serde_json::json!({
+            "rpc_url": format!("replay:{FEW_DEPLOYMENTS_FIXTURE}"),
+            "programdata_address": FEW_DEPLOYMENTS_PROGRAMDATA_ADDRESS,
+            "scan": {
+                "searched": 1,
+                "unrecoverable_signatures": [],
+                "undecoded_signatures": [],
+                "first_signature": first_signature,
+                "last_signature": last_signature,
+            },
+            "reducer": { "deployments": [], "failed_attempts": [] },
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn invoking_timeline_with_resume_flag_continues_below_the_checkpoint() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    write_timeline_checkpoint(
+        cache_dir.path(),
+        FEW_DEPLOYMENTS_NEWEST_SIGNATURE,
+        FEW_DEPLOYMENTS_NEWEST_SIGNATURE,
+    );
+
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--resume")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg("-vv")
+        .arg("timeline")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "resume",
+            "Invoking the timeline with the `--resume` flag when the history has not changed \
+             since the checkpoint should continue with the transactions older than it.",
+        )
+        .success()
+        .stdout(predicate::str::contains("316871932"))
+        .stdout(predicate::str::contains("316874697").not())
+        .stderr(predicate::str::contains(format!(
+            "Resuming the lookup of program_id: {RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS} \
+             after 1 transactions."
+        )));
+}
+
+#[test]
+fn invoking_timeline_with_resume_flag_refuses_a_checkpoint_older_than_the_history() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    write_timeline_checkpoint(
+        cache_dir.path(),
+        FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE,
+        FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE,
+    );
+
+    test_command()
+        .arg("--replay")
+        .arg(FEW_DEPLOYMENTS_FIXTURE)
+        .arg("--resume")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg("timeline")
+        .arg(RANDOM_DEVNET_PROGRAM_WITH_FEW_DEPLOYMENTS)
+        .assert()
+        .append_context(
+            "resume",
+            "Invoking the timeline with the `--resume` flag when the history gained transactions \
+             after the checkpoint was saved should fail, rather than leave them out.",
+        )
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(format!(
+            "The history has transactions newer than {FEW_DEPLOYMENTS_FAILED_UPGRADE_SIGNATURE}"
+        )));
+}
+
+#[test]
+fn invoking_with_resume_flag_continues_across_a_page_boundary() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let checkpoints_dir = cache_dir.path().join("checkpoints");
+    std::fs::create_dir_all(&checkpoints_dir).unwrap();
+    std::fs::write(
+        checkpoints_dir.join(format!("inception-{TWO_PAGE_PROGRAM_ID}.json")),
+        serde_json::json!({
+            "rpc_url": format!("replay:{TWO_PAGE_FIXTURE}"),
+            "programdata_address": "4sUBr1W9CDFpnG1aqTXJHLABFXSxjn2CrWBkXuaTVnTM",
+            "scan": {
+                "searched": 1,
+                "unrecoverable_signatures": [],
+                "undecoded_signatures": [],
+                "last_signature": TWO_PAGE_OLDER_PAGE_NEWEST_SIGNATURE,
+            },
+            "reducer": { "inception": null, "failed_attempts": [] },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    test_command()
+        .arg("--replay")
+        .arg(TWO_PAGE_FIXTURE)
+        .arg("--resume")
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg("-vvv")
+        .arg(TWO_PAGE_PROGRAM_ID)
+        .assert()
+        .append_context(
+            "resume",
+            "Invoking with the `--resume` flag when the checkpoint ends the older page should \
+             continue with the oldest transaction of the newer page.",
+        )
+        .success()
+        .stdout(predicate::str::diff("2023-11-14T22:13:40+00:00\n"))
+        .stderr(predicate::str::contains(format!(
+            "Resuming the lookup of program_id: {TWO_PAGE_PROGRAM_ID} after 1 transactions."
+        )))
+        .stderr(predicate::str::contains("Scanned 2 transactions"));
+}
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use chrono::prelude::*;
+use solception::{
+    init_tracing,
+    Deployment,
+    DeploymentKind,
+    DeploymentOutcome,
+    DeploymentTimeline,
+    InterruptFlag,
+    LastDeployment,
+    ProgramId,
+    ProvenanceReport,
+    SolanaQueries,
+    Solception,
+    SolceptionError,
+    TracingOptions,
+};
+
+const PROGRAM_ID: &str = "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD";
+const PROGRAMDATA_ADDRESS: &str = "4PQH9YmfuKrVyZaibkLYpJZPv2FPaybhq2GAuBcWMSBf";
+
+/// A gateway answering from fixed values, standing in for an embedding
+/// program's own data source.
+struct FixedGateway {
+    unrecoverable_signatures: Vec<String>,
+}
+
+impl FixedGateway {
+    fn block_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 7, 1, 12, 0, 0).unwrap()
+    }
+}
+
+impl SolanaQueries for FixedGateway {
+    fn get_provenance_report(&self, program_id: &ProgramId) -> Result<ProvenanceReport> {
+        Ok(ProvenanceReport {
+            program_id: *program_id,
+            programdata_address: PROGRAMDATA_ADDRESS.parse()?,
+            inception: Deployment {
+                kind: DeploymentKind::InitialDeploy,
+                slot: 84_000_000,
+                signature: "inception".to_string(),
+                block_time: Some(Self::block_time()),
+                authority: None,
+                fee_payer: "payer".to_string(),
+                outcome: DeploymentOutcome::Succeeded,
+            },
+            failed_attempts: vec![],
+            unrecoverable_signatures: self.unrecoverable_signatures.clone(),
+            undecoded_signatures: vec![],
+        })
+    }
+
+    fn get_deployment_timeline(&self, program_id: &ProgramId) -> Result<DeploymentTimeline> {
+        Err(SolceptionError::NoDeploymentsFound {
+            program_id: program_id.to_string(),
+        }
+        .into())
+    }
+
+    fn get_last_deployment(&self, program_id: &ProgramId) -> Result<LastDeployment> {
+        Ok(LastDeployment {
+            program_id: *program_id,
+            programdata_address: PROGRAMDATA_ADDRESS.parse()?,
+            slot: 90_000_000,
+            block_time: Self::block_time(),
+            upgrade_authority: None,
+            is_inception: false,
+        })
+    }
+}
+
+fn client_with_gateway(unrecoverable_signatures: Vec<String>, strict: bool) -> Solception {
+    Solception::builder()
+        .strict(strict)
+        .gateway(FixedGateway {
+            unrecoverable_signatures,
+        })
+        .build()
+        .expect("A client with an injected gateway should build without any RPC settings.")
+}
+
+#[test]
+fn injected_gateway_answers_lookups_with_typed_results() {
+    let solception = client_with_gateway(Vec::new(), false);
+    let program_id: ProgramId = PROGRAM_ID.parse().unwrap();
+
+    let report = solception.lookup_provenance(&program_id).unwrap();
+    assert_eq!(report.program_id, program_id);
+    assert_eq!(report.inception.slot, 84_000_000);
+    assert_eq!(
+        report.inception.block_time,
+        Some(FixedGateway::block_time())
+    );
+    assert!(report.is_complete());
+
+    let last_deployment = solception.lookup_last_deployment(&program_id).unwrap();
+    assert_eq!(last_deployment.slot, 90_000_000);
+}
+
+#[test]
+fn injected_gateway_errors_downcast_to_solception_errors() {
+    let solception = client_with_gateway(Vec::new(), false);
+
+    let error = solception
+        .lookup_timeline(&PROGRAM_ID.parse().unwrap())
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SolceptionError>(),
+        Some(SolceptionError::NoDeploymentsFound { .. })
+    ));
+}
+
+#[test]
+fn strict_client_refuses_an_incomplete_history_from_an_injected_gateway() {
+    let solception = client_with_gateway(vec!["unrecoverable".to_string()], true);
+
+    let error = solception
+        .lookup_provenance(&PROGRAM_ID.parse().unwrap())
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SolceptionError>(),
+        Some(SolceptionError::HistoryTruncated { .. })
+    ));
+}
+
+#[test]
+fn malformed_program_ids_fail_to_parse_as_invalid_pubkeys() {
+    let error = "not-a-program".parse::<ProgramId>().unwrap_err();
+    assert!(matches!(error, SolceptionError::InvalidPubkey { .. }));
+    assert_eq!(error.exit_code(), 3);
+}
+
+#[test]
+fn provenance_reports_round_trip_through_serde() {
+    let solception = client_with_gateway(Vec::new(), false);
+    let report = solception
+        .lookup_provenance(&PROGRAM_ID.parse().unwrap())
+        .unwrap();
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains(&format!("\"program_id\":\"{PROGRAM_ID}\"")));
+    assert_eq!(
+        serde_json::from_str::<ProvenanceReport>(&json).unwrap(),
+        report
+    );
+}
+
+#[test]
+fn tracing_helper_keeps_an_already_installed_subscriber() {
+    assert!(init_tracing(&TracingOptions::default()).is_ok());
+    assert!(init_tracing(&TracingOptions {
+        debug_level: 4,
+        ..TracingOptions::default()
+    })
+    .is_err());
+}
+
+#[test]
+fn interrupt_flag_is_owned_and_cleared_by_the_caller() {
+    let interrupt = InterruptFlag::default();
+    let solception = Solception::builder()
+        .interrupt(interrupt.clone())
+        .gateway(FixedGateway {
+            unrecoverable_signatures: vec![],
+        })
+        .build()
+        .unwrap();
+
+    interrupt.clone().request();
+    assert!(interrupt.is_requested());
+
+    interrupt.clear();
+    assert!(!interrupt.is_requested());
+    assert!(solception
+        .lookup_provenance(&PROGRAM_ID.parse().unwrap())
+        .is_ok());
+}